@@ -0,0 +1,41 @@
+//! Views of exceptions as trait objects, see [Exception::provide_dyn].
+
+use crate::Exception;
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+/// Request for a view of an exception as a trait object, filled by [Exception::provide_dyn]
+pub struct DynRequest<'a> {
+    target: TypeId,
+    // Points to an `Option<&'a D>` where `D` is the type identified by `target`
+    slot: *mut (),
+    // `'a` must be invariant, otherwise a shorter lived reference could be written to the slot
+    _lifetime: PhantomData<fn(&'a ()) -> &'a ()>,
+}
+
+impl<'a> DynRequest<'a> {
+    /// Provide the exception viewed as `D`, it is used only if `D` is the requested trait object
+    pub fn provide<D: ?Sized + 'static>(&mut self, value: &'a D) -> &mut Self {
+        if TypeId::of::<D>() == self.target {
+            // SAFETY: `slot` points to an `Option<&'a D>`, its type is identified by `target`
+            unsafe { *(self.slot as *mut Option<&'a D>) = Some(value) }
+        }
+        self
+    }
+}
+
+impl dyn Exception {
+    /// View the exception as the trait object `D`, for example `dyn Retryable`.\
+    /// The derive macro declares the available views with
+    /// `#[exception(as_dyn(Retryable, HasStatus))]`.
+    pub fn as_dyn<D: ?Sized + 'static>(&self) -> Option<&D> {
+        let mut slot: Option<&D> = None;
+        let mut request = DynRequest {
+            target: TypeId::of::<D>(),
+            slot: &mut slot as *mut Option<&D> as *mut (),
+            _lifetime: PhantomData,
+        };
+        self.provide_dyn(&mut request);
+        slot
+    }
+}
@@ -0,0 +1,172 @@
+//! Normal panics caught by [catch](crate::catch).
+
+use crate::Exception;
+use std::any::Any;
+use std::fmt;
+use std::ops::Deref;
+use std::panic::Location;
+
+/// A normal panic caught by [catch](crate::catch), the payload plus where the panic happened.\
+/// It dereferences to the payload so it can be downcast like the `Box<dyn Any>` returned by
+/// [std::panic::catch_unwind].
+pub struct CaughtPanic {
+    payload: Box<dyn Any + Send>,
+    location: Option<PanicLocation>,
+}
+
+impl CaughtPanic {
+    pub(crate) fn new(payload: Box<dyn Any + Send>, location: Option<PanicLocation>) -> Self {
+        CaughtPanic { payload, location }
+    }
+    /// The message of the panic, if the payload is a string as with `panic!("...")`
+    pub fn message(&self) -> Option<&str> {
+        message(&*self.payload)
+    }
+    /// Where the panic happened.\
+    /// Only known if the panic hook of [catch](crate::catch) saw it, it is missing for example
+    /// when the panic crossed threads.
+    pub fn location(&self) -> Option<&PanicLocation> {
+        self.location.as_ref()
+    }
+    /// The panic payload
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+    /// Take the panic payload, to resume unwinding with [std::panic::resume_unwind] for example
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+    pub(crate) fn into_parts(self) -> (Box<dyn Any + Send>, Option<PanicLocation>) {
+        (self.payload, self.location)
+    }
+}
+
+impl Deref for CaughtPanic {
+    type Target = dyn Any + Send;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.payload
+    }
+}
+
+impl fmt::Debug for CaughtPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CaughtPanic")
+            .field("message", &self.message())
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl fmt::Display for CaughtPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("panic")?;
+        if let Some(message) = self.message() {
+            write!(f, ": {}", message)?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        Ok(())
+    }
+}
+
+/// The message of a panic payload, if it is a string
+pub(crate) fn message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+}
+
+/// A normal panic converted into an exception by [catch_uniform](crate::catch_uniform)
+#[derive(Debug)]
+pub struct PanicException {
+    message: Option<String>,
+    location: Option<PanicLocation>,
+}
+
+impl PanicException {
+    /// The message of the panic, if its payload was a string.\
+    /// [Exception::message] also includes the location of the panic.
+    pub fn panic_message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+    /// Where the panic happened, see [CaughtPanic::location]
+    pub fn location(&self) -> Option<&PanicLocation> {
+        self.location.as_ref()
+    }
+}
+
+impl From<CaughtPanic> for PanicException {
+    fn from(panic: CaughtPanic) -> Self {
+        PanicException {
+            message: panic.message().map(Into::into),
+            location: panic.location,
+        }
+    }
+}
+
+impl fmt::Display for PanicException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message.as_deref().unwrap_or("panic"))?;
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        Ok(())
+    }
+}
+
+impl Exception for PanicException {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn message(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+/// Source location of a panic, an owned copy of [std::panic::Location]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl PanicLocation {
+    /// The source file of the panic
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+    /// The line of the panic
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+    /// The column of the panic
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl From<&Location<'_>> for PanicLocation {
+    fn from(location: &Location<'_>) -> Self {
+        PanicLocation {
+            file: location.file().into(),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl fmt::Display for PanicLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
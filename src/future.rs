@@ -0,0 +1,241 @@
+//! Catching exceptions thrown while polling futures and streams.
+//!
+//! Every poll of the wrapped future runs inside [catch], an exception thrown by the future
+//! completes the wrapper with the error instead of unwinding through the executor.
+
+use crate::{catch, CatchError};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future returned by [catch_async]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct CatchFuture<F> {
+    future: F,
+}
+
+impl<F> CatchFuture<F> {
+    fn future(self: Pin<&mut Self>) -> Pin<&mut F> {
+        // SAFETY: `future` is structurally pinned, it is never moved out of a pinned wrapper
+        unsafe { self.map_unchecked_mut(|this| &mut this.future) }
+    }
+}
+
+impl<F: Future> Future for CatchFuture<F> {
+    type Output = Result<F::Output, CatchError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.future();
+        match catch(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(value)) => Poll::Ready(Ok(value)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+}
+
+/// Async version of [catch], exceptions and panics raised while polling `future` are returned
+/// as errors.\
+/// The future must not be polled again once it completed.
+///
+/// ```rust
+///    use std::future::Future;
+///    use trycatch::{catch_async, throw, CatchError, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    struct Offline;
+///
+///    async fn fetch() -> u32 {
+///        throw(Offline)
+///    }
+///
+///    async fn run() {
+///        let r = catch_async(fetch()).await;
+///        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Offline>()));
+///    }
+///
+///    // any executor works, this one polls until the future completes
+///    let mut future = Box::pin(run());
+///    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+///    while future.as_mut().poll(&mut cx).is_pending() {}
+/// ```
+pub fn catch_async<F: Future>(future: F) -> CatchFuture<F> {
+    CatchFuture { future }
+}
+
+/// Asynchronous sequence of values.\
+/// The standard library doesn't provide one yet, this trait has the same shape as the
+/// `Stream` trait of the `futures` crate so adapting a stream takes a single forwarding impl.
+pub trait Stream {
+    /// Values yielded by the stream
+    type Item;
+    /// Attempt to pull the next value, `None` once the stream is exhausted
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+/// Stream returned by [catch_stream]
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct CatchStream<S> {
+    stream: S,
+    done: bool,
+}
+
+impl<S> CatchStream<S> {
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut S>, &mut bool) {
+        // SAFETY: `stream` is structurally pinned, `done` is not
+        unsafe {
+            let this = self.get_unchecked_mut();
+            (Pin::new_unchecked(&mut this.stream), &mut this.done)
+        }
+    }
+}
+
+impl<S: Stream> Stream for CatchStream<S> {
+    type Item = Result<S::Item, CatchError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let (stream, done) = self.project();
+        if *done {
+            return Poll::Ready(None);
+        }
+        match catch(AssertUnwindSafe(|| stream.poll_next(cx))) {
+            Ok(Poll::Ready(Some(value))) => Poll::Ready(Some(Ok(value))),
+            Ok(Poll::Ready(None)) => {
+                *done = true;
+                Poll::Ready(None)
+            }
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(e) => {
+                *done = true;
+                Poll::Ready(Some(Err(e)))
+            }
+        }
+    }
+}
+
+/// Stream version of [catch_async], an exception or panic raised while polling `stream` is
+/// yielded as an error and ends the stream.
+pub fn catch_stream<S: Stream>(stream: S) -> CatchStream<S> {
+    CatchStream {
+        stream,
+        done: false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{throw, Exception};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(value) => return value,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Pending once before completing, so exceptions are thrown on a later poll
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[derive(Debug, Exception)]
+    struct Offline(u32);
+
+    async fn fetch(id: u32) -> u32 {
+        YieldNow(false).await;
+        if id == 0 {
+            throw(Offline(id));
+        }
+        id * 2
+    }
+
+    #[test]
+    fn future() {
+        assert!(matches!(block_on(catch_async(fetch(2))), Ok(4)));
+
+        let r = block_on(catch_async(async {
+            let a = fetch(1).await;
+            a + fetch(0).await
+        }));
+        match r {
+            Err(CatchError::Exception(e)) => {
+                assert!(matches!(e.downcast_ref::<Offline>(), Some(Offline(0))))
+            }
+            _ => panic!("expected an exception"),
+        }
+
+        let r = block_on(catch_async(async {
+            YieldNow(false).await;
+            panic!("boom")
+        }));
+        assert!(matches!(r, Err(CatchError::Panic(_))));
+
+        // the other way around, a future that completes inside a synchronous catch
+        assert!(matches!(catch(|| block_on(fetch(3))), Ok(6)));
+    }
+
+    struct Counter(u32);
+
+    impl Stream for Counter {
+        type Item = u32;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u32>> {
+            self.0 += 1;
+            match self.0 {
+                3 => throw(Offline(3)),
+                n => Poll::Ready(Some(n)),
+            }
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let mut stream = Box::pin(catch_stream(Counter(0)));
+        let mut next = || block_on(NextItem(stream.as_mut()));
+        assert!(matches!(next(), Some(Ok(1))));
+        assert!(matches!(next(), Some(Ok(2))));
+        assert!(matches!(next(), Some(Err(CatchError::Exception(e))) if e.is::<Offline>()));
+        assert!(next().is_none());
+    }
+
+    struct NextItem<'a, S>(Pin<&'a mut S>);
+
+    impl<S: Stream> Future for NextItem<'_, S> {
+        type Output = Option<S::Item>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            self.0.as_mut().poll_next(cx)
+        }
+    }
+}
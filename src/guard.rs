@@ -0,0 +1,234 @@
+//! Scope guards that run while an exception passes through a frame.
+//!
+//! [throw](crate::throw) shares the exception it throws with the current thread until the
+//! exception is caught, so guards dropped by the unwinding can inspect it without catching it.\
+//! The exception is kept behind a lock until the first time it is accessed through the caught
+//! `Box<dyn Exception>`, from then on it belongs to the catcher and guards no longer see it.\
+//! The exception only belongs to the unwinding it was thrown in. One swallowed by
+//! [std::panic::catch_unwind] is forgotten when it is dropped, when a `catch` call or a guard
+//! starts outside of an unwinding, or when a normal panic goes through the hook installed by
+//! `catch`.
+
+use crate::Exception;
+use std::cell::{OnceCell, RefCell};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+type Shared = Arc<Mutex<Option<Box<dyn Exception>>>>;
+
+thread_local! {
+    static IN_FLIGHT: RefCell<Option<Shared>> = const { RefCell::new(None) };
+}
+
+fn lock(shared: &Shared) -> MutexGuard<'_, Option<Box<dyn Exception>>> {
+    // A guard that panicked while unwinding aborts the process, the data is never left invalid
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Exception thrown by [throw](crate::throw), shared with the guards of its thread while it is
+/// in flight.
+pub(crate) struct InFlight {
+    shared: Shared,
+    owned: OnceCell<Box<dyn Exception>>,
+}
+
+impl InFlight {
+    pub(crate) fn new(exception: Box<dyn Exception>) -> Self {
+        InFlight {
+            shared: Arc::new(Mutex::new(Some(exception))),
+            owned: OnceCell::new(),
+        }
+    }
+    /// Share the exception with the guards of the current thread, it is being thrown again
+    pub(crate) fn arm(&mut self) {
+        if let Some(exception) = self.owned.take() {
+            *lock(&self.shared) = Some(exception);
+        }
+        let shared = self.shared.clone();
+        IN_FLIGHT.with(|in_flight| *in_flight.borrow_mut() = Some(shared));
+    }
+    /// Take the exception back from the guards, it was caught
+    pub(crate) fn get(&self) -> &dyn Exception {
+        &**self.owned.get_or_init(|| {
+            disarm(&self.shared);
+            lock(&self.shared)
+                .take()
+                .expect("in flight exception is always available until it is caught")
+        })
+    }
+    pub(crate) fn get_mut(&mut self) -> &mut dyn Exception {
+        self.get();
+        &mut **self.owned.get_mut().unwrap()
+    }
+    pub(crate) fn into_inner(mut self) -> Box<dyn Exception> {
+        self.get();
+        self.owned.take().unwrap()
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        // Dropped by whoever caught it, possibly without ever looking at it
+        disarm(&self.shared);
+        let exception = lock(&self.shared).take();
+        drop(exception);
+    }
+}
+
+// Stop sharing `shared` with the guards of the current thread
+fn disarm(shared: &Shared) {
+    let _ = IN_FLIGHT.try_with(|in_flight| {
+        let mut in_flight = in_flight.borrow_mut();
+        if in_flight.as_ref().is_some_and(|s| Arc::ptr_eq(s, shared)) {
+            *in_flight = None;
+        }
+    });
+}
+
+/// Forget the exception in flight, a normal panic started so it was swallowed
+pub(crate) fn forget() {
+    let _ = IN_FLIGHT.try_with(|in_flight| in_flight.borrow_mut().take());
+}
+
+// An exception in flight outside of an unwinding was swallowed, it belongs to an unwinding
+// that ended
+fn forget_swallowed() {
+    if !thread::panicking() {
+        forget();
+    }
+}
+
+/// Clears the exception in flight while a `catch` call runs, only exceptions thrown inside of
+/// it are seen by its guards.\
+/// The exception is restored when the call ends, so catches nested in guards or `Drop` impls
+/// don't hide it from the guards that run after them.
+pub(crate) struct Restore(Option<Shared>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        let _ = IN_FLIGHT.try_with(|in_flight| *in_flight.borrow_mut() = previous);
+    }
+}
+
+pub(crate) fn save() -> Restore {
+    let previous = IN_FLIGHT.with(|in_flight| in_flight.borrow_mut().take());
+    // Outside of an unwinding there is nothing to restore
+    Restore(previous.filter(|_| thread::panicking()))
+}
+
+// Run `f` with the exception unwinding the current thread, if any.\
+// The exception is taken out of the lock while `f` runs, guards dropped inside `f` see `None`.
+fn with_in_flight<R>(f: impl FnOnce(Option<&dyn Exception>) -> R) -> R {
+    let shared = if thread::panicking() {
+        IN_FLIGHT
+            .try_with(|in_flight| in_flight.borrow().clone())
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+    match shared {
+        Some(shared) => {
+            let exception = lock(&shared).take();
+            let r = f(exception.as_deref());
+            *lock(&shared) = exception;
+            r
+        }
+        None => f(None),
+    }
+}
+
+/// Guard running a closure when dropped, created by [defer!](crate::defer)
+#[must_use = "the closure runs when the guard is dropped, bind it to a variable"]
+pub struct Defer<F: FnOnce()>(Option<F>);
+
+impl<F: FnOnce()> Defer<F> {
+    /// Run `f` when the guard is dropped, at the end of the scope or while unwinding
+    pub fn new(f: F) -> Self {
+        Defer(Some(f))
+    }
+    /// Drop the guard without running the closure
+    pub fn cancel(mut self) {
+        self.0 = None;
+    }
+}
+
+impl<F: FnOnce()> Drop for Defer<F> {
+    fn drop(&mut self) {
+        if let Some(f) = self.0.take() {
+            f()
+        }
+    }
+}
+
+/// Guard returned by [on_unwind]
+#[must_use = "the closure runs when the guard is dropped, bind it to a variable"]
+pub struct OnUnwind<F: FnOnce(Option<&dyn Exception>)>(Option<F>);
+
+impl<F: FnOnce(Option<&dyn Exception>)> Drop for OnUnwind<F> {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+        if let Some(f) = self.0.take() {
+            with_in_flight(f)
+        }
+    }
+}
+
+/// Run `f` if the guard is dropped while unwinding, with the exception passing through if the
+/// unwinding is caused by one, `None` for normal panics.\
+/// The exception is not caught, it keeps unwinding once the guard ran.
+///
+/// ```rust
+///    use trycatch::{catch, on_unwind, throw, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    struct Conflict;
+///
+///    let mut log = Vec::new();
+///    let _ = catch(std::panic::AssertUnwindSafe(|| {
+///        let _guard = on_unwind(|e| log.push(e.map(|e| e.name())));
+///        throw(Conflict)
+///    }));
+///    assert_eq!(log, [Some("Conflict")]);
+/// ```
+pub fn on_unwind<F: FnOnce(Option<&dyn Exception>)>(f: F) -> OnUnwind<F> {
+    forget_swallowed();
+    OnUnwind(Some(f))
+}
+
+/// Guard returned by [on_exception]
+#[must_use = "the closure runs when the guard is dropped, bind it to a variable"]
+pub struct OnException<E: Exception, F: FnOnce(&E)> {
+    f: Option<F>,
+    _exception: PhantomData<fn(&E)>,
+}
+
+impl<E: Exception, F: FnOnce(&E)> Drop for OnException<E, F> {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+        if let Some(f) = self.f.take() {
+            with_in_flight(|e| {
+                if let Some(e) = e.and_then(|e| e.downcast_ref::<E>()) {
+                    f(e)
+                }
+            })
+        }
+    }
+}
+
+/// Run `f` if the guard is dropped while an exception of type `E` passes through, to log or
+/// roll back the work of the frame.\
+/// The exception is not caught, it keeps unwinding once the guard ran.
+pub fn on_exception<E: Exception, F: FnOnce(&E)>(f: F) -> OnException<E, F> {
+    forget_swallowed();
+    OnException {
+        f: Some(f),
+        _exception: PhantomData,
+    }
+}
@@ -0,0 +1,109 @@
+//! Panic hook management for [catch](crate::catch).
+//!
+//! A single process wide hook is installed the first time `catch` is called, it wraps the hook
+//! that was active at that time.\
+//! The hook can't be changed while the thread is panicking, a `catch` called by a `Drop` impl
+//! during an unwinding leaves the install to a later call and its exceptions are reported by the
+//! current hook.\
+//! Only exceptions thrown inside `catch` are silenced, everything else is forwarded to the
+//! wrapped hook.\
+//! Each thread keeps track of how many `catch` calls it is currently inside of, so concurrent
+//! and nested catches never need to swap the global hook.\
+//! The location of a normal panic inside `catch` is kept in a thread local until `catch`
+//! takes it, the panic payload itself can't carry it.\
+//! It is recorded with the type and message of the payload, a panic swallowed by
+//! [std::panic::catch_unwind] doesn't lend its location to a payload resumed afterwards.
+
+use crate::{caught, guard, Exception, PanicLocation};
+use std::any::{Any, TypeId};
+use std::cell::Cell;
+use std::panic;
+use std::sync::Once;
+use std::thread;
+
+thread_local! {
+    static CATCH_DEPTH: Cell<usize> = const { Cell::new(0) };
+    static LAST_PANIC: Cell<Option<LastPanic>> = const { Cell::new(None) };
+}
+
+/// Location of the last normal panic and the payload it belongs to
+struct LastPanic {
+    payload: TypeId,
+    message: Option<String>,
+    location: PanicLocation,
+}
+
+impl LastPanic {
+    fn new(payload: &(dyn Any + Send), location: PanicLocation) -> Self {
+        LastPanic {
+            payload: payload.type_id(),
+            message: caught::message(payload).map(Into::into),
+            location,
+        }
+    }
+    fn matches(&self, payload: &(dyn Any + Send)) -> bool {
+        self.payload == payload.type_id() && self.message.as_deref() == caught::message(payload)
+    }
+}
+
+/// Marks the current thread as being inside a `catch` call while alive.\
+/// The location of a panic unwinding around the `catch` call is set aside until it returns.
+pub(crate) struct CatchGuard(Option<LastPanic>);
+
+impl Drop for CatchGuard {
+    fn drop(&mut self) {
+        CATCH_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        LAST_PANIC.with(|last| last.set(self.0.take()));
+    }
+}
+
+/// Install the trycatch hook if needed and enter a `catch` call on the current thread.
+pub(crate) fn enter_catch() -> CatchGuard {
+    static INSTALL: Once = Once::new();
+    // `take_hook` and `set_hook` panic on a panicking thread, try again on the next call
+    if !thread::panicking() {
+        INSTALL.call_once(install);
+    }
+    CATCH_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    CatchGuard(LAST_PANIC.with(Cell::take))
+}
+
+fn install() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        // The thread local can already be destroyed if we panic while the thread exits
+        let in_catch = CATCH_DEPTH.try_with(Cell::get).unwrap_or(0) > 0;
+        let is_exception = panic_info
+            .payload()
+            .downcast_ref::<Box<dyn Exception>>()
+            .is_some();
+        if !is_exception {
+            guard::forget();
+        }
+        if in_catch && !is_exception {
+            let last = panic_info
+                .location()
+                .map(|location| LastPanic::new(panic_info.payload(), location.into()));
+            let _ = LAST_PANIC.try_with(|last_panic| last_panic.set(last));
+        }
+        if !(in_catch && is_exception) {
+            // Normal panics keep going through the user hook, backtraces included
+            previous(panic_info)
+        }
+    }));
+}
+
+/// Take the location of the last normal panic seen inside `catch` on the current thread, if it
+/// belongs to `payload`
+pub(crate) fn take_panic_location(payload: &(dyn Any + Send)) -> Option<PanicLocation> {
+    LAST_PANIC
+        .with(Cell::take)
+        .filter(|last| last.matches(payload))
+        .map(|last| last.location)
+}
+
+/// Restore the location of a panic that resumes unwinding, the hook doesn't see it again
+pub(crate) fn set_panic_location(payload: &(dyn Any + Send), location: Option<PanicLocation>) {
+    let last = location.map(|location| LastPanic::new(payload, location));
+    LAST_PANIC.with(|last_panic| last_panic.set(last));
+}
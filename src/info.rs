@@ -0,0 +1,142 @@
+//! Metadata recorded by [throw](crate::throw) at the throw site.
+
+use crate::guard::InFlight;
+use crate::{DynRequest, Exception};
+use std::any::{Any, TypeId};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use std::fmt;
+use std::panic::Location;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::thread;
+use std::time::SystemTime;
+
+// 0: follow `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE`, 1: always capture, 2: never capture
+static CAPTURE_BACKTRACE: AtomicU8 = AtomicU8::new(0);
+
+/// Enable or disable backtrace capture in [throw](crate::throw).\
+/// By default backtraces are captured only if `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` are set,
+/// calling this function overrides the environment variables.
+pub fn set_capture_backtrace(enabled: bool) {
+    CAPTURE_BACKTRACE.store(if enabled { 1 } else { 2 }, Ordering::Relaxed);
+}
+
+/// Where, when and by which thread an exception was thrown.\
+/// Retrieved with [Exception::throw_info] on the exception returned by [catch](crate::catch).
+#[derive(Debug)]
+pub struct ThrowInfo {
+    location: &'static Location<'static>,
+    backtrace: Option<Backtrace>,
+    thread_name: Option<String>,
+    timestamp: SystemTime,
+}
+
+impl ThrowInfo {
+    fn capture(location: &'static Location<'static>) -> Self {
+        let backtrace = match CAPTURE_BACKTRACE.load(Ordering::Relaxed) {
+            0 => Some(Backtrace::capture()),
+            1 => Some(Backtrace::force_capture()),
+            _ => None,
+        }
+        .filter(|backtrace| backtrace.status() == BacktraceStatus::Captured);
+        ThrowInfo {
+            location,
+            backtrace,
+            thread_name: thread::current().name().map(Into::into),
+            timestamp: SystemTime::now(),
+        }
+    }
+    /// The source location of the [throw](crate::throw) call
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+    /// The backtrace of the [throw](crate::throw) call, if it was captured
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+    /// The name of the thread that threw the exception, if it had one
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+    /// When the exception was thrown
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+}
+
+// Envelope thrown by `throw`, it behaves like the user exception plus the throw metadata.
+// Rethrown exceptions get a new envelope without metadata, the guards can't reach the one they
+// were thrown in once it is caught.
+pub(crate) struct Thrown {
+    exception: InFlight,
+    info: Option<ThrowInfo>,
+}
+
+impl Thrown {
+    pub(crate) fn new(exception: Box<dyn Exception>, location: &'static Location<'static>) -> Self {
+        Thrown {
+            exception: InFlight::new(exception),
+            info: Some(ThrowInfo::capture(location)),
+        }
+    }
+    pub(crate) fn resumed(exception: Box<dyn Exception>) -> Self {
+        Thrown {
+            exception: InFlight::new(exception),
+            info: None,
+        }
+    }
+    /// Share the exception with the guards of the current thread before unwinding
+    pub(crate) fn arm(&mut self) {
+        self.exception.arm();
+    }
+}
+
+impl fmt::Debug for Thrown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.exception.get().fmt(f)
+    }
+}
+
+impl Exception for Thrown {
+    fn name(&self) -> &'static str {
+        self.exception.get().name()
+    }
+    fn full_name(&self) -> &'static str {
+        self.exception.get().full_name()
+    }
+    fn variant_name(&self) -> Option<&'static str> {
+        self.exception.get().variant_name()
+    }
+    fn kind(&self) -> &'static str {
+        self.exception.get().kind()
+    }
+    fn extends(&self, parent: TypeId) -> bool {
+        self.exception.get().extends(parent)
+    }
+    fn provide_dyn<'a>(&'a self, request: &mut DynRequest<'a>) {
+        self.exception.get().provide_dyn(request)
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self.exception.into_inner().into_any()
+    }
+    fn as_any(&self) -> &dyn Any {
+        self.exception.get().as_any()
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.exception.get_mut().as_any_mut()
+    }
+    fn cause(&self) -> Option<&dyn Exception> {
+        self.exception.get().cause()
+    }
+    fn throw_info(&self) -> Option<&ThrowInfo> {
+        self.info
+            .as_ref()
+            .or_else(|| self.exception.get().throw_info())
+    }
+    fn message(&self) -> Option<String> {
+        self.exception.get().message()
+    }
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        self.exception.get().as_error()
+    }
+}
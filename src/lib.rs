@@ -11,7 +11,7 @@
 //!    use trycatch::{Exception,ExceptionDowncast,throw,catch,CatchError};
 //!
 //!    // Create our custom exception and implement `Exception` trait on it
-//!    #[derive(Exception)]
+//!    #[derive(Debug, Exception)]
 //!    struct MyE;
 //!
 //!    // Our example of a call stack.
@@ -39,100 +39,827 @@
 //! ```
 //!
 
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::error::Error;
 use std::fmt;
 use std::panic::{self, UnwindSafe};
 
+use __private::resume;
+
+// Lets the derive macros refer to `::trycatch` inside this crate too
+extern crate self as trycatch;
+
+mod as_dyn;
+mod caught;
+mod future;
+mod guard;
+mod hook;
+mod info;
+mod or_throw;
+pub mod thread;
+mod throws;
+mod typed;
+
+pub use as_dyn::DynRequest;
+pub use caught::{CaughtPanic, PanicException, PanicLocation};
+pub use future::{catch_async, catch_stream, CatchFuture, CatchStream, Stream};
+pub use guard::{on_exception, on_unwind, Defer, OnException, OnUnwind};
+pub use info::{set_capture_backtrace, ThrowInfo};
+pub use or_throw::{ErrorException, NoneError, OrThrow};
+pub use throws::{set_report_undeclared, UndeclaredException};
+pub use typed::{catch_typed, catch_typed2, catch_typed3, Can};
+
 /// The result of [catch]\
 /// It can be either an exception or a normal panic
 #[derive(Debug)]
 pub enum CatchError {
     /// User exception
     Exception(Box<dyn Exception>),
-    /// Normal panic
-    Panic(Box<dyn Any + Send>),
+    /// Normal panic, its payload and location
+    Panic(CaughtPanic),
 }
 
-/// Runs a function and catch its exceptions and panics.
-pub fn catch<T>(expr: impl FnOnce() -> T + UnwindSafe) -> Result<T, CatchError> {
-    // The the exception needs to be specified as a type argument.
-    // This method removes panic error messages from exceptions while the guard is alive.\
-    fn register_catch() -> impl Drop {
-        let last_hook = std::panic::take_hook();
-
-        std::panic::set_hook(Box::new(|panic_info| {
-            if panic_info
-                .payload()
-                .downcast_ref::<Box<dyn Exception>>()
-                .is_some()
-            {
-                // Don't print anything
-            } else {
-                // ~= Normal flow
-                eprintln!("{}", panic_info)
-            }
-        }));
-        type PanicHook = dyn for<'r, 's> Fn(&'r std::panic::PanicInfo<'s>) + Send + Sync;
-        struct Unregister(Option<Box<PanicHook>>);
-        impl Drop for Unregister {
-            fn drop(&mut self) {
-                std::panic::set_hook(self.0.take().unwrap());
+impl fmt::Display for CatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatchError::Exception(e) => {
+                write!(f, "exception {}", e.name())?;
+                if e.message().is_some() || e.as_error().is_some() {
+                    write!(f, ": {}", e)?;
+                }
+                Ok(())
             }
+            CatchError::Panic(p) => p.fmt(f),
         }
-        Unregister(Some(last_hook))
     }
-    {
-        let _g = register_catch();
-        std::panic::catch_unwind(expr).map_err(|e| {
-            if e.is::<Box<dyn Exception>>() {
-                CatchError::Exception(*e.downcast::<Box<dyn Exception>>().unwrap())
-            } else {
-                CatchError::Panic(e)
-            }
-        })
+}
+
+impl CatchError {
+    /// The message of a normal panic, if its payload is a string as with `panic!("...")`
+    pub fn panic_message(&self) -> Option<&str> {
+        match self {
+            CatchError::Exception(_) => None,
+            CatchError::Panic(p) => p.message(),
+        }
+    }
+    /// Where a normal panic happened, see [CaughtPanic::location]
+    pub fn panic_location(&self) -> Option<&PanicLocation> {
+        match self {
+            CatchError::Exception(_) => None,
+            CatchError::Panic(p) => p.location(),
+        }
+    }
+    /// Resume unwinding with the original payload, outer handlers see exactly what was thrown.\
+    /// Use it instead of `throw(e)` to propagate a caught exception.
+    pub fn rethrow(self) -> ! {
+        resume(self)
+    }
+}
+
+impl Error for CatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CatchError::Exception(e) => Some(e),
+            CatchError::Panic(_) => None,
+        }
     }
-    //}};
+}
+
+/// Runs a function and catch its exceptions and panics.
+///
+/// The first call installs a process wide panic hook that silences exceptions thrown inside
+/// `catch` and defers to the previously installed hook for everything else, normal panics
+/// inside `catch` included. A call made while the thread is panicking, from a `Drop` impl, can't
+/// install it and leaves it to the next call.\
+/// Set your own panic hook before catching, a hook set afterwards replaces the trycatch one.
+pub fn catch<T>(expr: impl FnOnce() -> T + UnwindSafe) -> Result<T, CatchError> {
+    let _g = hook::enter_catch();
+    let _in_flight = guard::save();
+    std::panic::catch_unwind(expr).map_err(|e| {
+        if e.is::<Box<dyn Exception>>() {
+            CatchError::Exception(*e.downcast::<Box<dyn Exception>>().unwrap())
+        } else {
+            let location = hook::take_panic_location(&*e);
+            CatchError::Panic(CaughtPanic::new(e, location))
+        }
+    })
 }
 
 /// User defined exception needs to implement this trait.\
 /// The concrete exception type can be retrieved via [ExceptionDowncast::downcast]
 pub trait Exception: 'static + Send + fmt::Debug {
     /// The name of the exception, useful to figure out the type of dyn exception before
-    /// downcasting it to a concrete type
+    /// downcasting it to a concrete type.\
+    /// Different exception types can share a name, use [full_name](Exception::full_name) or
+    /// [exception_type_id](trait.Exception.html#method.exception_type_id) to tell them apart.\
+    /// Defaults to the type name without its module path, the derive macro uses the type name or
+    /// `#[exception(name = "...")]`.
     fn name(&self) -> &'static str {
-        unimplemented!()
+        let full_name = self.full_name();
+        let path = match full_name.find('<') {
+            Some(generics) => &full_name[..generics],
+            None => full_name,
+        };
+        match path.rfind("::") {
+            Some(separator) => &path[separator + 2..],
+            None => path,
+        }
+    }
+    /// The name of the enum variant, for exceptions that are enums.\
+    /// Generated by the derive macro.
+    fn variant_name(&self) -> Option<&'static str> {
+        None
+    }
+    /// The name of the exception, followed by the variant for enums: `"NetErr::Timeout"`.\
+    /// Useful to dispatch on specific variants without downcasting.
+    fn kind(&self) -> &'static str {
+        self.name()
+    }
+    /// Returns true if the exception type extends the exception type identified by `parent`,
+    /// directly or through its own parents.\
+    /// The derive macro declares the parent with `#[exception(parent = Type)]`, parent chains
+    /// must not form a cycle, a cyclic hierarchy panics the first time it is walked.
+    fn extends(&self, parent: TypeId) -> bool {
+        let _ = parent;
+        false
+    }
+    /// Same as [extends](Exception::extends) without an exception value, used to walk up the
+    /// hierarchy.
+    fn type_extends(parent: TypeId) -> bool
+    where
+        Self: Sized,
+    {
+        let _ = parent;
+        false
+    }
+    /// Provide views of the exception as trait objects, retrieved with
+    /// [as_dyn](trait.Exception.html#method.as_dyn).\
+    /// The derive macro provides the traits listed in `#[exception(as_dyn(Retryable))]`.
+    fn provide_dyn<'a>(&'a self, request: &mut DynRequest<'a>) {
+        let _ = request;
+    }
+    /// The fully qualified name of the exception type, module path and generics included
+    fn full_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
     }
     /// Cast Box<dyn Exception> to <dyn Any>, useful inorder to retrieve the concrete exception type.
     fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    /// Cast &dyn Exception to &dyn Any, useful inorder to check the concrete exception type
+    /// without consuming it.
+    fn as_any(&self) -> &dyn Any;
+    /// Cast &mut dyn Exception to &mut dyn Any, useful inorder to modify the concrete exception.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// The exception that caused this one, if any.\
+    /// The derive macro returns the field marked with `#[cause]`.
+    fn cause(&self) -> Option<&dyn Exception> {
+        None
+    }
+    /// Where and when the exception was thrown.\
+    /// Recorded by [throw], it is available on the exceptions returned by [catch].
+    fn throw_info(&self) -> Option<&ThrowInfo> {
+        None
+    }
+    /// Human readable description of the exception, used by its `Display` implementation.\
+    /// The derive macro builds it from `#[exception(message = "file {path} not found")]`, on the
+    /// struct or on each enum variant, and implements `Display` with it.
+    fn message(&self) -> Option<String> {
+        None
+    }
+    /// View of the exception as a standard error, if its type implements `std::error::Error`.\
+    /// The derive macro opts into it with `#[exception(error)]`.
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
 }
 impl Exception for Box<dyn Exception> {
     fn name(&self) -> &'static str {
         (**self).name()
     }
+    fn full_name(&self) -> &'static str {
+        (**self).full_name()
+    }
+    fn variant_name(&self) -> Option<&'static str> {
+        (**self).variant_name()
+    }
+    fn kind(&self) -> &'static str {
+        (**self).kind()
+    }
+    fn extends(&self, parent: TypeId) -> bool {
+        (**self).extends(parent)
+    }
+    fn provide_dyn<'a>(&'a self, request: &mut DynRequest<'a>) {
+        (**self).provide_dyn(request)
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        Exception::into_any(*self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        (**self).as_any()
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        (**self).as_any_mut()
+    }
+    fn cause(&self) -> Option<&dyn Exception> {
+        (**self).cause()
+    }
+    fn throw_info(&self) -> Option<&ThrowInfo> {
+        (**self).throw_info()
+    }
+    fn message(&self) -> Option<String> {
+        (**self).message()
+    }
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        (**self).as_error()
+    }
+}
+
+/// Displays the exception message, falling back to its error description or its name
+impl fmt::Display for dyn Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.message(), self.as_error()) {
+            (Some(message), _) => f.write_str(&message),
+            (None, Some(error)) => fmt::Display::fmt(error, f),
+            (None, None) => f.write_str(self.name()),
+        }
+    }
+}
+
+/// Caught exceptions can be used as standard errors, for example with `?` into `Box<dyn Error>`.\
+/// With `std::error::Error` in scope, call [Exception::cause] as `Exception::cause(&e)` since
+/// `Error` has a deprecated method with the same name.
+impl Error for Box<dyn Exception> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.as_error() {
+            Some(error) => error.source(),
+            None => Exception::cause(self)?.as_error(),
+        }
+    }
+}
+
+impl dyn Exception {
+    /// The `TypeId` of the concrete exception type.\
+    /// Not named `type_id` since `Any::type_id` would return the id of `Box<dyn Exception>`.
+    pub fn exception_type_id(&self) -> TypeId {
+        self.as_any().type_id()
+    }
+    /// Returns true if the concrete exception type is `E`.\
+    /// Prefer it to comparing names, different exception types can share a name.
+    pub fn is<E: Exception>(&self) -> bool {
+        self.as_any().is::<E>()
+    }
+    /// Returns true if the exception is of type `E` or extends it, see [Exception::extends]
+    pub fn is_a<E: Exception>(&self) -> bool {
+        self.is::<E>() || self.extends(TypeId::of::<E>())
+    }
+    /// Returns a reference to the concrete exception if it is of type `E`
+    pub fn downcast_ref<E: Exception>(&self) -> Option<&E> {
+        self.as_any().downcast_ref::<E>()
+    }
+    /// Returns a mutable reference to the concrete exception if it is of type `E`
+    pub fn downcast_mut<E: Exception>(&mut self) -> Option<&mut E> {
+        self.as_any_mut().downcast_mut::<E>()
+    }
+    /// Iterate over this exception and its causes, starting with this exception
+    pub fn chain(&self) -> Chain<'_> {
+        Chain { next: Some(self) }
+    }
+    /// Find the first exception of type `E` in the chain, this exception included
+    pub fn find_cause<E: Exception>(&self) -> Option<&E> {
+        self.chain().find_map(|e| e.downcast_ref::<E>())
+    }
+    /// Downcast the direct cause of this exception to `E`
+    pub fn downcast_cause_ref<E: Exception>(&self) -> Option<&E> {
+        self.cause()?.downcast_ref::<E>()
+    }
+}
+
+/// Iterator over an exception and its causes, created by [chain](trait.Exception.html#method.chain)
+pub struct Chain<'a> {
+    next: Option<&'a dyn Exception>,
+}
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a dyn Exception;
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        self.next = current.cause();
+        Some(current)
+    }
+}
+
+// Exception thrown by [throw_with_cause], it behaves like the outer exception.
+struct Caused {
+    exception: Box<dyn Exception>,
+    cause: Box<dyn Exception>,
+}
+impl fmt::Debug for Caused {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.exception.fmt(f)
+    }
+}
+impl Exception for Caused {
+    fn name(&self) -> &'static str {
+        self.exception.name()
+    }
+    fn full_name(&self) -> &'static str {
+        self.exception.full_name()
+    }
+    fn variant_name(&self) -> Option<&'static str> {
+        self.exception.variant_name()
+    }
+    fn kind(&self) -> &'static str {
+        self.exception.kind()
+    }
+    fn extends(&self, parent: TypeId) -> bool {
+        self.exception.extends(parent)
+    }
+    fn provide_dyn<'a>(&'a self, request: &mut DynRequest<'a>) {
+        self.exception.provide_dyn(request)
+    }
     fn into_any(self: Box<Self>) -> Box<dyn Any> {
-        self
+        self.exception.into_any()
+    }
+    fn as_any(&self) -> &dyn Any {
+        (*self.exception).as_any()
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        (*self.exception).as_any_mut()
+    }
+    fn cause(&self) -> Option<&dyn Exception> {
+        Some(&*self.cause)
+    }
+    fn throw_info(&self) -> Option<&ThrowInfo> {
+        self.exception.throw_info()
+    }
+    fn message(&self) -> Option<String> {
+        self.exception.message()
+    }
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        self.exception.as_error()
     }
 }
 
-/// Throw an exception that can be caught with [catch]
+/// Throw an exception that can be caught with [catch]\
+/// The location of the call, and a backtrace if enabled, are recorded in [Exception::throw_info]
+#[track_caller]
 pub fn throw(e: impl Exception) -> ! {
-    panic::panic_any(Box::new(e) as Box<dyn Exception>);
+    let mut thrown = info::Thrown::new(Box::new(e), panic::Location::caller());
+    thrown.arm();
+    panic::panic_any(Box::new(thrown) as Box<dyn Exception>);
+}
+
+/// Throw an exception `e` caused by another exception `cause`.\
+/// The caught exception behaves like `e`, `cause` is available through [Exception::cause].
+#[track_caller]
+pub fn throw_with_cause(e: impl Exception, cause: impl Exception) -> ! {
+    throw(Caused {
+        exception: Box::new(e),
+        cause: Box::new(cause),
+    })
+}
+/// Resume unwinding with a caught exception, outer handlers see exactly what was thrown.\
+/// The [ThrowInfo] of the original [throw] call is kept.
+pub fn rethrow(e: Box<dyn Exception>) -> ! {
+    resume(CatchError::Exception(e))
 }
-pub use trycatch_derive::Exception;
+
+pub use trycatch_derive::{throws, Exception, ExceptionSet};
 /// Helper trait that allows downcasting *Box\<dyn Exception\>* to a concrete exception type
 pub trait ExceptionDowncast {
     /// Downcast Box<dyn Exception> to a concrete exception type
     fn downcast<E: Exception>(self) -> E;
-    /// Try to downcast Box<dyn Exception> to a concrete exception type, if it fails it returns Self as Box<dyn Any>
-    fn try_downcast<E: Exception>(self) -> Result<E, Box<dyn Any>>;
+    /// Try to downcast Box<dyn Exception> to a concrete exception type, if it fails it returns Self
+    /// so other types can be tried
+    fn try_downcast<E: Exception>(self) -> Result<E, Self>
+    where
+        Self: Sized;
 }
 impl ExceptionDowncast for Box<dyn Exception> {
     fn downcast<T: Exception + 'static>(self) -> T {
         self.try_downcast()
             .expect("Downcasting failed, mismatched type")
     }
-    fn try_downcast<T: Exception + 'static>(self) -> Result<T, Box<dyn Any>> {
-        self.into_any().downcast::<T>().map(|this| *this)
+    fn try_downcast<T: Exception + 'static>(self) -> Result<T, Self> {
+        if self.is::<T>() {
+            Ok(*self.into_any().downcast::<T>().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// The result of [catch_only2], one of the two caught exception types
+#[derive(Debug)]
+pub enum OneOf2<A, B> {
+    /// Exception of the first type
+    First(A),
+    /// Exception of the second type
+    Second(B),
+}
+
+/// The result of [catch_only3], one of the three caught exception types
+#[derive(Debug)]
+pub enum OneOf3<A, B, C> {
+    /// Exception of the first type
+    First(A),
+    /// Exception of the second type
+    Second(B),
+    /// Exception of the third type
+    Third(C),
+}
+
+/// Same as [catch] but only exceptions are caught, normal panics keep unwinding with their
+/// original payload.\
+/// Exceptions are control flow and panics are bugs, a bug still crashes the test or thread
+/// instead of being handled with the exceptions. The panic hook output is unchanged.
+///
+/// ```rust
+///    use trycatch::{catch, catch_exceptions, throw, CatchError, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    struct Invalid;
+///
+///    assert!(catch_exceptions(|| throw(Invalid)).unwrap_err().is::<Invalid>());
+///    let r = catch(|| catch_exceptions(|| panic!("bug")));
+///    assert!(matches!(r, Err(CatchError::Panic(_))));
+/// ```
+pub fn catch_exceptions<T>(expr: impl FnOnce() -> T + UnwindSafe) -> Result<T, Box<dyn Exception>> {
+    catch(expr).map_err(exception_or_resume)
+}
+
+/// Same as [catch] but normal panics are converted into a [PanicException], every failure
+/// is an exception.\
+/// `unwrap()` on `None` or an out of bounds index can then be handled like the other exceptions.
+///
+/// ```rust
+///    use trycatch::{catch_uniform, PanicException};
+///
+///    let v: Vec<u8> = Vec::new();
+///    let e = catch_uniform(|| v[1]).unwrap_err();
+///    let panic = e.downcast_ref::<PanicException>().unwrap();
+///    assert!(panic.panic_message().unwrap().contains("out of bounds"));
+/// ```
+pub fn catch_uniform<T>(expr: impl FnOnce() -> T + UnwindSafe) -> Result<T, Box<dyn Exception>> {
+    catch(expr).map_err(|e| match e {
+        CatchError::Exception(e) => e,
+        CatchError::Panic(p) => Box::new(PanicException::from(p)),
+    })
+}
+
+/// Runs a function and catch only exceptions of type `E`.\
+/// Other exceptions and normal panics keep unwinding with their original payload, so an outer
+/// [catch] still sees them.
+pub fn catch_only<E: Exception, T>(expr: impl FnOnce() -> T + UnwindSafe) -> Result<T, E> {
+    catch(expr).map_err(|e| {
+        let e = exception_or_resume(e);
+        e.try_downcast::<E>()
+            .unwrap_or_else(|e| resume(CatchError::Exception(e)))
+    })
+}
+
+/// Same as [catch_only] but catches exceptions of type `A` or `B`
+pub fn catch_only2<A: Exception, B: Exception, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+) -> Result<T, OneOf2<A, B>> {
+    catch(expr).map_err(|e| {
+        let e = exception_or_resume(e);
+        e.try_downcast::<A>()
+            .map(OneOf2::First)
+            .or_else(|e| e.try_downcast::<B>().map(OneOf2::Second))
+            .unwrap_or_else(|e| resume(CatchError::Exception(e)))
+    })
+}
+
+/// Same as [catch_only] but catches exceptions of type `A`, `B` or `C`
+pub fn catch_only3<A: Exception, B: Exception, C: Exception, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+) -> Result<T, OneOf3<A, B, C>> {
+    catch(expr).map_err(|e| {
+        let e = exception_or_resume(e);
+        e.try_downcast::<A>()
+            .map(OneOf3::First)
+            .or_else(|e| e.try_downcast::<B>().map(OneOf3::Second))
+            .or_else(|e| e.try_downcast::<C>().map(OneOf3::Third))
+            .unwrap_or_else(|e| resume(CatchError::Exception(e)))
+    })
+}
+
+/// Runs a function and catch only exceptions of type `E` or of a type that extends it, see
+/// [Exception::extends].\
+/// Other exceptions and normal panics keep unwinding with their original payload.
+///
+/// ```rust
+///    use trycatch::{catch_is_a, throw, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    struct IoException;
+///    #[derive(Debug, Exception)]
+///    #[exception(parent = IoException)]
+///    struct FileNotFound;
+///
+///    let e = catch_is_a::<IoException, _>(|| throw(FileNotFound)).unwrap_err();
+///    assert!(e.is::<FileNotFound>());
+/// ```
+pub fn catch_is_a<E: Exception, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+) -> Result<T, Box<dyn Exception>> {
+    catch(expr).map_err(|e| {
+        let e = exception_or_resume(e);
+        if e.is_a::<E>() {
+            e
+        } else {
+            resume(CatchError::Exception(e))
+        }
+    })
+}
+
+/// Runs a function and catch only exceptions that can be viewed as the trait object `D`, see
+/// [as_dyn](trait.Exception.html#method.as_dyn).\
+/// Other exceptions and normal panics keep unwinding with their original payload.
+///
+/// ```rust
+///    use trycatch::{catch_dyn, throw, Exception};
+///
+///    trait Retryable {
+///        fn attempts(&self) -> u32;
+///    }
+///
+///    #[derive(Debug, Exception)]
+///    #[exception(as_dyn(Retryable))]
+///    struct Timeout;
+///    impl Retryable for Timeout {
+///        fn attempts(&self) -> u32 {
+///            3
+///        }
+///    }
+///
+///    let e = catch_dyn::<dyn Retryable, _>(|| throw(Timeout)).unwrap_err();
+///    assert_eq!(e.as_dyn::<dyn Retryable>().unwrap().attempts(), 3);
+/// ```
+pub fn catch_dyn<D: ?Sized + 'static, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+) -> Result<T, Box<dyn Exception>> {
+    catch(expr).map_err(|e| {
+        let e = exception_or_resume(e);
+        if e.as_dyn::<D>().is_some() {
+            e
+        } else {
+            resume(CatchError::Exception(e))
+        }
+    })
+}
+
+/// Runs a function and catch only exceptions of type `E` accepted by `filter`.\
+/// Other exceptions and normal panics keep unwinding with their original payload.\
+/// For enum exceptions a `matches!` filter catches specific variants, a misspelled or renamed
+/// variant doesn't compile.
+///
+/// ```rust
+///    use trycatch::{catch, catch_if, throw, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    enum NetErr {
+///        Timeout,
+///        Refused,
+///    }
+///
+///    let timeout = |e: &NetErr| matches!(e, NetErr::Timeout);
+///    assert!(catch_if(|| throw(NetErr::Timeout), timeout).is_err());
+///    // `Refused` is not handled, it reaches the outer catch
+///    let r = catch(|| catch_if(|| throw(NetErr::Refused), timeout));
+///    assert!(r.is_err());
+/// ```
+pub fn catch_if<E: Exception, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+    filter: impl FnOnce(&E) -> bool,
+) -> Result<T, E> {
+    catch(expr).map_err(|e| {
+        let e = exception_or_resume(e);
+        if e.downcast_ref::<E>().is_some_and(filter) {
+            e.downcast::<E>()
+        } else {
+            resume(CatchError::Exception(e))
+        }
+    })
+}
+
+/// Runs a function and converts its exceptions into `R`, for example an enum deriving
+/// [ExceptionSet].\
+/// Normal panics keep unwinding, use [catch_into_with] to convert them too.
+///
+/// ```rust
+///    use trycatch::{catch_into, throw, Exception, ExceptionSet};
+///
+///    #[derive(Debug, Exception)]
+///    struct IoErr;
+///    #[derive(Debug, Exception)]
+///    struct ParseErr;
+///
+///    #[derive(Debug, ExceptionSet)]
+///    enum AppError {
+///        Io(IoErr),
+///        Parse(ParseErr),
+///    }
+///
+///    let r: Result<(), AppError> = catch_into(|| throw(ParseErr));
+///    assert!(matches!(r, Err(AppError::Parse(ParseErr))));
+/// ```
+pub fn catch_into<R: From<Box<dyn Exception>>, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+) -> Result<T, R> {
+    catch(expr).map_err(|e| R::from(exception_or_resume(e)))
+}
+
+/// Same as [catch_into] but normal panics are converted into `R` with `on_panic`
+pub fn catch_into_with<R: From<Box<dyn Exception>>, T>(
+    expr: impl FnOnce() -> T + UnwindSafe,
+    on_panic: impl FnOnce(CaughtPanic) -> R,
+) -> Result<T, R> {
+    catch(expr).map_err(|e| match e {
+        CatchError::Exception(e) => R::from(e),
+        CatchError::Panic(p) => on_panic(p),
+    })
+}
+
+// Normal panics are not handled by the typed catches, resume unwinding them untouched.
+fn exception_or_resume(e: CatchError) -> Box<dyn Exception> {
+    match e {
+        CatchError::Exception(e) => e,
+        CatchError::Panic(p) => resume(CatchError::Panic(p)),
+    }
+}
+
+/// Java/Python style try catch block built on [catch].
+///
+/// Each `catch (e: Type)` arm handles exceptions of that type, they are tried in order.\
+/// `catch panic(p)` handles normal panics, `p` is the [CaughtPanic].\
+/// `else` runs only if the `try` block didn't throw, `finally` runs on every path, even when a
+/// catch arm throws.\
+/// Exceptions and panics that are not handled keep unwinding with their original payload.
+///
+/// The try block and the catch arms evaluate to the value of the whole expression.\
+/// Note that they run inside closures, so `return` and `?` inside them only leave the block.
+///
+/// ```rust
+///    use trycatch::{throw, try_catch, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    struct IoErr;
+///    #[derive(Debug, Exception)]
+///    struct ParseErr(String);
+///
+///    let mut cleaned = false;
+///    let value = try_catch! {
+///        try {
+///            throw(ParseErr("nan".into()))
+///        } catch (e: IoErr) {
+///            0
+///        } catch (e: ParseErr) {
+///            e.0.len()
+///        } finally {
+///            cleaned = true;
+///        }
+///    };
+///    assert_eq!(value, 3);
+///    assert!(cleaned);
+/// ```
+#[macro_export]
+macro_rules! try_catch {
+    (
+        try $try:block
+        $(catch ($e:ident : $ty:ty) $handler:block)*
+        $(catch panic ($p:pat) $panic_handler:block)?
+        $(else $else:block)?
+        $(finally $finally:block)?
+    ) => {{
+        let result = $crate::catch($crate::__private::AssertUnwindSafe(|| {
+            match $crate::catch($crate::__private::AssertUnwindSafe(|| $try)) {
+                ::std::result::Result::Ok(value) => {
+                    $($else;)?
+                    value
+                }
+                ::std::result::Result::Err(error) => $crate::try_catch!(
+                    @handle error;
+                    $(($e: $ty) $handler)*;
+                    $(($p) $panic_handler)?
+                ),
+            }
+        }));
+        $($finally)?
+        match result {
+            ::std::result::Result::Ok(value) => value,
+            ::std::result::Result::Err(error) => $crate::__private::resume(error),
+        }
+    }};
+    (
+        @handle $error:ident;
+        ($e:ident : $ty:ty) $handler:block $(($es:ident : $tys:ty) $handlers:block)*;
+        $($panic:tt)*
+    ) => {
+        match $error {
+            $crate::CatchError::Exception(exception) => {
+                match <::std::boxed::Box<dyn $crate::Exception> as $crate::ExceptionDowncast>::try_downcast::<$ty>(exception) {
+                    ::std::result::Result::Ok($e) => $handler,
+                    ::std::result::Result::Err(exception) => {
+                        let $error = $crate::CatchError::Exception(exception);
+                        $crate::try_catch!(@handle $error; $(($es: $tys) $handlers)*; $($panic)*)
+                    }
+                }
+            }
+            $error => $crate::try_catch!(@handle $error; $(($es: $tys) $handlers)*; $($panic)*),
+        }
+    };
+    (@handle $error:ident; ; ($p:pat) $panic_handler:block) => {
+        match $error {
+            $crate::CatchError::Panic(payload) => {
+                let $p = payload;
+                $panic_handler
+            }
+            $error => $crate::__private::resume($error),
+        }
+    };
+    (@handle $error:ident; ; ) => {
+        $crate::__private::resume($error)
+    };
+}
+
+/// Run the statements at the end of the enclosing scope, on normal exit and while unwinding.\
+/// Use [on_exception] or [on_unwind] to run them only when an exception or panic passes through.
+///
+/// ```rust
+///    use trycatch::{catch, defer, throw, Exception};
+///    use std::cell::Cell;
+///
+///    #[derive(Debug, Exception)]
+///    struct Failed;
+///
+///    let cleaned = Cell::new(0);
+///    let _ = catch(std::panic::AssertUnwindSafe(|| {
+///        defer! { cleaned.set(cleaned.get() + 1) }
+///        throw(Failed)
+///    }));
+///    assert_eq!(cleaned.get(), 1);
+/// ```
+#[macro_export]
+macro_rules! defer {
+    ($($body:tt)*) => {
+        let _defer = $crate::Defer::new(|| {
+            $($body)*
+        });
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+    pub use crate::throws::declared;
+    pub use std::panic::AssertUnwindSafe;
+
+    // Deeper hierarchies are assumed to be cycles, `parent = ...` declarations pointing back
+    // to a descendant would otherwise overflow the stack.
+    const MAX_HIERARCHY_DEPTH: usize = 128;
+
+    thread_local! {
+        static HIERARCHY_DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    struct Depth;
+
+    impl Drop for Depth {
+        fn drop(&mut self) {
+            HIERARCHY_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        }
+    }
+
+    // `type_extends` of an exception declared with `#[exception(parent = P)]`.
+    pub fn extends_parent<P: Exception>(parent: TypeId) -> bool {
+        let depth = HIERARCHY_DEPTH.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get()
+        });
+        let _depth = Depth;
+        if depth > MAX_HIERARCHY_DEPTH {
+            panic!(
+                "the exception hierarchy of `{}` is cyclic, parent declarations must not loop",
+                std::any::type_name::<P>()
+            );
+        }
+        parent == TypeId::of::<P>() || P::type_extends(parent)
+    }
+
+    // Resume unwinding with the original payload of a caught exception or panic.
+    pub fn resume(e: CatchError) -> ! {
+        match e {
+            CatchError::Exception(e) => {
+                let mut resumed = info::Thrown::resumed(e);
+                resumed.arm();
+                panic::resume_unwind(Box::new(Box::new(resumed) as Box<dyn Exception>))
+            }
+            CatchError::Panic(p) => {
+                let (payload, location) = p.into_parts();
+                hook::set_panic_location(&*payload, location);
+                panic::resume_unwind(payload)
+            }
+        }
     }
 }
 
@@ -142,7 +869,7 @@ mod test {
 
     #[test]
     fn it() {
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct MyE {}
         fn a() {
             fn b() {
@@ -173,7 +900,7 @@ mod test {
 
     #[test]
     fn simple() {
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct E;
         let r = catch(|| throw(E));
         if let Err(CatchError::Exception(e)) = r {
@@ -185,11 +912,12 @@ mod test {
 
     #[test]
     fn multi_exception() {
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct A;
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct B;
 
+        #[allow(unreachable_code)]
         fn c() {
             throw(B);
             throw(A);
@@ -209,7 +937,7 @@ mod test {
 
     #[test]
     fn simpler() {
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct A;
 
         assert!(matches!(catch(|| throw(A)), Err(CatchError::Exception(_))))
@@ -217,9 +945,9 @@ mod test {
 
     #[test]
     fn complex() {
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct A(B);
-        #[derive(Exception)]
+        #[derive(Debug, Exception)]
         struct B;
 
         let excep_b = if let Err(CatchError::Exception(excep_b)) = catch(|| {
@@ -239,4 +967,977 @@ mod test {
         };
         assert_eq!(excep_b.name(), "B");
     }
+
+    #[test]
+    fn cause_chain() {
+        #[derive(Debug, Exception)]
+        struct Io;
+        #[derive(Debug, Exception)]
+        struct Parse(#[cause] Io);
+        #[derive(Debug, Exception)]
+        struct Config {
+            path: &'static str,
+            #[cause]
+            source: Parse,
+        }
+        #[derive(Debug, Exception)]
+        enum Startup {
+            Config {
+                #[cause]
+                config: Config,
+            },
+            Timeout,
+        }
+
+        let r = catch(|| {
+            throw(Startup::Config {
+                config: Config {
+                    path: "app.toml",
+                    source: Parse(Io),
+                },
+            })
+        });
+        if let Err(CatchError::Exception(e)) = r {
+            let names: Vec<_> = e.chain().map(|e| e.name()).collect();
+            assert_eq!(names, ["Startup", "Config", "Parse", "Io"]);
+            assert_eq!(e.downcast_cause_ref::<Config>().unwrap().path, "app.toml");
+            assert!(e.downcast_cause_ref::<Io>().is_none());
+            assert!(e.find_cause::<Io>().is_some());
+            assert!(e.find_cause::<Startup>().is_some());
+        } else {
+            panic!("test failed");
+        }
+        assert!(Startup::Timeout.cause().is_none());
+
+        let r = catch(|| {
+            throw_with_cause(
+                Parse(Io),
+                Config {
+                    path: "",
+                    source: Parse(Io),
+                },
+            )
+        });
+        if let Err(CatchError::Exception(e)) = r {
+            assert_eq!(e.name(), "Parse");
+            assert_eq!(e.chain().count(), 4);
+            assert!(matches!(e.downcast::<Parse>(), Parse(Io)));
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn error() {
+        #[derive(Debug, Exception)]
+        #[exception(error)]
+        struct NotFound(#[cause] Io);
+        impl fmt::Display for NotFound {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("file not found")
+            }
+        }
+        impl Error for NotFound {}
+        #[derive(Debug, Exception)]
+        struct Io;
+
+        fn read() -> Result<(), Box<dyn Error>> {
+            catch(|| throw(NotFound(Io)))?;
+            Ok(())
+        }
+        let e = read().unwrap_err();
+        assert_eq!(e.to_string(), "exception NotFound: file not found");
+        assert_eq!(e.source().unwrap().to_string(), "file not found");
+
+        let r = catch(|| throw(Io));
+        assert_eq!(r.as_ref().unwrap_err().to_string(), "exception Io");
+        if let Err(CatchError::Exception(e)) = r {
+            assert_eq!(e.to_string(), "Io");
+            assert!(e.source().is_none());
+        } else {
+            panic!("test failed");
+        }
+        let line = line!() + 1;
+        let e = catch(|| panic!("this is an intended test panic")).unwrap_err();
+        let location = format!("{}:{}:", file!(), line);
+        assert!(e.to_string().starts_with(&format!(
+            "panic: this is an intended test panic at {}",
+            location
+        )));
+        assert!(format!("{:?}", e).contains("this is an intended test panic"));
+    }
+
+    #[test]
+    fn into() {
+        #[derive(Debug, Exception)]
+        struct Io;
+        #[derive(Debug, Exception)]
+        struct Parse(u8);
+        #[derive(Debug, Exception)]
+        struct Other;
+        #[derive(Debug, ExceptionSet)]
+        enum AppError {
+            Io(Io),
+            Parse(Parse),
+        }
+
+        assert!(matches!(catch_into::<AppError, _>(|| 1), Ok(1)));
+        assert!(matches!(
+            catch_into::<AppError, _>(|| throw(Io)),
+            Err(AppError::Io(Io))
+        ));
+        assert!(matches!(
+            catch_into::<AppError, _>(|| throw(Parse(2))),
+            Err(AppError::Parse(Parse(2)))
+        ));
+
+        // unknown exceptions keep propagating
+        let r = catch(|| catch_into::<AppError, _>(|| throw(Other)));
+        if let Err(CatchError::Exception(e)) = r {
+            assert!(matches!(e.downcast::<Other>(), Other));
+        } else {
+            panic!("test failed");
+        }
+
+        #[derive(Debug)]
+        enum WithPanic {
+            Exception(Box<dyn Exception>),
+            Panic,
+        }
+        impl From<Box<dyn Exception>> for WithPanic {
+            fn from(e: Box<dyn Exception>) -> Self {
+                WithPanic::Exception(e)
+            }
+        }
+        assert!(matches!(
+            catch_into_with(
+                || panic!("this is an intended test panic"),
+                |_| WithPanic::Panic
+            ),
+            Err(WithPanic::Panic)
+        ));
+        assert!(matches!(
+            catch_into_with(|| throw(Io), |_| WithPanic::Panic),
+            Err(WithPanic::Exception(e)) if e.name() == "Io"
+        ));
+    }
+
+    #[test]
+    fn downcast_ref() {
+        #[derive(Debug, Exception)]
+        struct A(u8);
+        #[derive(Debug, Exception)]
+        struct B;
+
+        if let Err(CatchError::Exception(mut e)) = catch(|| throw(A(1))) {
+            assert!(e.is::<A>());
+            assert!(!e.is::<B>());
+            assert!(e.downcast_ref::<B>().is_none());
+            assert_eq!(e.downcast_ref::<A>().unwrap().0, 1);
+            e.downcast_mut::<A>().unwrap().0 = 2;
+            assert!(e.downcast_mut::<B>().is_none());
+
+            // a failed downcast gives the exception back
+            let e = e.try_downcast::<B>().unwrap_err();
+            assert_eq!(e.try_downcast::<A>().unwrap().0, 2);
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn identity() {
+        mod io {
+            #[derive(Debug, crate::Exception)]
+            #[exception(name = "IoError")]
+            pub struct Error;
+        }
+        mod parse {
+            #[derive(Debug)]
+            pub struct Error<T>(pub T);
+            // hand written impl relying on the default names
+            impl<T: std::fmt::Debug + Send + 'static> crate::Exception for Error<T> {
+                fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+                    self
+                }
+                fn as_any(&self) -> &dyn std::any::Any {
+                    self
+                }
+                fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                    self
+                }
+            }
+        }
+
+        let io = catch(|| throw(io::Error)).unwrap_err();
+        let parse = catch(|| throw(parse::Error(1u8))).unwrap_err();
+        if let (CatchError::Exception(io), CatchError::Exception(parse)) = (io, parse) {
+            assert_eq!(io.name(), "IoError");
+            assert_eq!(parse.name(), "Error");
+            assert_eq!(io.full_name(), "trycatch::test::identity::io::Error");
+            assert_eq!(
+                parse.full_name(),
+                "trycatch::test::identity::parse::Error<u8>"
+            );
+            assert_eq!(io.exception_type_id(), TypeId::of::<io::Error>());
+            assert_eq!(parse.exception_type_id(), TypeId::of::<parse::Error<u8>>());
+            assert!(io.is::<io::Error>());
+            assert!(!parse.is::<io::Error>());
+            assert!(!parse.is::<parse::Error<u16>>());
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn derive_generics() {
+        #[derive(Debug, Exception)]
+        struct Wrapper<T>(#[cause] T)
+        where
+            T: Exception;
+        #[derive(Debug, Exception)]
+        enum Either<L: fmt::Debug, R> {
+            Left(L),
+            Right { value: R },
+        }
+        #[derive(Debug, Exception)]
+        struct Inner;
+        #[derive(Debug, ExceptionSet)]
+        enum Set {
+            Wrapper(Wrapper<Inner>),
+            Either(Either<u8, String>),
+        }
+
+        let r = catch_into::<Set, _>(|| throw(Wrapper(Inner)));
+        if let Err(Set::Wrapper(e)) = r {
+            assert_eq!(e.name(), "Wrapper");
+            assert_eq!((&e as &dyn Exception).chain().count(), 2);
+        } else {
+            panic!("test failed");
+        }
+        assert!(matches!(
+            catch_into::<Set, _>(|| throw(Either::<u8, String>::Left(1))),
+            Err(Set::Either(Either::Left(1)))
+        ));
+        let right = Either::<u8, &str>::Right { value: "right" };
+        assert!(matches!(right, Either::Right { value: "right" }));
+        assert_eq!(
+            right.full_name(),
+            "trycatch::test::derive_generics::Either<u8, &str>"
+        );
+    }
+
+    #[test]
+    fn derive_message() {
+        #[derive(Debug, Exception)]
+        #[exception(name = "FileNotFound", message = "file {path:?} not found {{{path}}}")]
+        struct NotFound {
+            path: &'static str,
+        }
+        #[derive(Debug, Exception)]
+        #[exception(name = "NetworkError")]
+        enum Net {
+            #[exception(message = "timed out after {0}s")]
+            Timeout(u64),
+            #[exception(message = "{host}:{port} refused the connection")]
+            Refused {
+                host: String,
+                port: u16,
+            },
+            Unknown,
+        }
+
+        let e = NotFound { path: "a.txt" };
+        assert_eq!(e.name(), "FileNotFound");
+        assert_eq!(e.message().unwrap(), "file \"a.txt\" not found {a.txt}");
+        assert_eq!(e.to_string(), e.message().unwrap());
+
+        assert_eq!(Net::Timeout(3).to_string(), "timed out after 3s");
+        let refused = Net::Refused {
+            host: "localhost".into(),
+            port: 80,
+        };
+        assert_eq!(refused.to_string(), "localhost:80 refused the connection");
+        assert_eq!(Net::Unknown.message(), None);
+        assert_eq!(Net::Unknown.to_string(), "NetworkError");
+
+        if let Err(CatchError::Exception(e)) = catch(|| throw(Net::Timeout(1))) {
+            assert_eq!(e.name(), "NetworkError");
+            assert_eq!(e.to_string(), "timed out after 1s");
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn variants() {
+        #[derive(Debug, Exception)]
+        #[exception(name = "NetErr")]
+        enum Net {
+            Timeout,
+            Refused(u16),
+            Reset { after: u64 },
+        }
+        #[derive(Debug, Exception)]
+        struct Other;
+
+        assert_eq!(Net::Timeout.variant_name(), Some("Timeout"));
+        assert_eq!(Net::Refused(80).kind(), "NetErr::Refused");
+        assert_eq!(Net::Reset { after: 1 }.kind(), "NetErr::Reset");
+        assert_eq!(Other.variant_name(), None);
+        assert_eq!(Other.kind(), "Other");
+
+        if let Err(CatchError::Exception(e)) = catch(|| throw(Net::Refused(80))) {
+            assert_eq!(e.kind(), "NetErr::Refused");
+            assert_eq!(e.variant_name(), Some("Refused"));
+        } else {
+            panic!("test failed");
+        }
+
+        let refused = |e: &Net| matches!(e, Net::Refused(..));
+        assert!(matches!(
+            catch_if(|| throw(Net::Refused(80)), refused),
+            Err(Net::Refused(80))
+        ));
+        let r = catch(|| catch_if(|| throw(Net::Timeout), refused));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.kind() == "NetErr::Timeout"));
+        let r = catch(|| catch_if(|| throw(Other), refused));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Other>()));
+
+        let r = catch_if(
+            || throw(Net::Reset { after: 3 }),
+            |e: &Net| matches!(e, Net::Reset { after } if *after > 2),
+        );
+        assert!(matches!(r, Err(Net::Reset { after: 3 })));
+    }
+
+    #[test]
+    fn hierarchy() {
+        #[derive(Debug, Exception)]
+        struct IoException;
+        #[derive(Debug, Exception)]
+        #[exception(parent = IoException)]
+        struct FileException;
+        #[derive(Debug, Exception)]
+        #[exception(parent = FileException)]
+        enum FileNotFound {
+            Missing,
+        }
+        #[derive(Debug, Exception)]
+        #[exception(parent = IoException)]
+        struct Timeout;
+        #[derive(Debug, Exception)]
+        struct Unrelated;
+
+        let missing: Box<dyn Exception> = Box::new(FileNotFound::Missing);
+        assert!(missing.is_a::<FileNotFound>());
+        assert!(missing.is_a::<FileException>());
+        assert!(missing.is_a::<IoException>());
+        assert!(!missing.is_a::<Timeout>());
+        assert!(!missing.is_a::<Unrelated>());
+        let io: Box<dyn Exception> = Box::new(IoException);
+        assert!(!io.is_a::<FileException>());
+        let timeout: Box<dyn Exception> = Box::new(Timeout);
+        assert!(!timeout.is_a::<FileException>());
+        assert!(timeout.is_a::<IoException>());
+
+        let e = catch_is_a::<IoException, _>(|| throw(FileNotFound::Missing)).unwrap_err();
+        assert!(e.is::<FileNotFound>());
+        let e = catch_is_a::<FileException, _>(|| throw(FileException)).unwrap_err();
+        assert!(e.is::<FileException>());
+
+        #[derive(Debug, Exception)]
+        #[exception(parent = Pong)]
+        struct Ping;
+        #[derive(Debug, Exception)]
+        #[exception(parent = Ping)]
+        struct Pong;
+        let r = catch(|| Ping.extends(TypeId::of::<Unrelated>()));
+        assert!(matches!(r, Err(CatchError::Panic(p)) if p.message().unwrap().contains("cyclic")));
+
+        // siblings and unrelated exceptions keep propagating
+        let r = catch(|| catch_is_a::<FileException, _>(|| throw(Timeout)));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Timeout>()));
+        let r = catch(|| catch_is_a::<IoException, _>(|| throw(Unrelated)));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Unrelated>()));
+    }
+
+    #[test]
+    fn throws() {
+        #[derive(Debug, Exception)]
+        struct Empty;
+        #[derive(Debug, Exception)]
+        struct Invalid(char);
+        #[derive(Debug, Exception)]
+        #[exception(parent = Empty)]
+        struct Blank;
+        #[derive(Debug, Exception)]
+        struct Unexpected;
+
+        #[throws(Empty, Invalid)]
+        fn parse(s: &str) -> u32 {
+            if s.is_empty() {
+                throw(Empty);
+            }
+            if s.trim().is_empty() {
+                throw(Blank);
+            }
+            if s == "?" {
+                throw(Unexpected);
+            }
+            let mut n = 0;
+            for c in s.chars() {
+                match c.to_digit(10) {
+                    Some(d) => n = n * 10 + d,
+                    None => throw(Invalid(c)),
+                }
+            }
+            n
+        }
+
+        struct Parser(u32);
+        impl Parser {
+            #[throws(Invalid)]
+            fn digit(&mut self, c: char) -> Result<u32, u32> {
+                if c == '-' {
+                    return Err(self.0);
+                }
+                self.0 = c.to_digit(10).unwrap_or_else(|| throw(Invalid(c)));
+                Ok(self.0)
+            }
+            #[throws(Invalid, method)]
+            fn new(start: char) -> Self {
+                let mut parser = Parser(0);
+                let _ = parser.digit(start);
+                parser
+            }
+            // a free `parse` is in scope as well, the companion must not call it
+            #[throws(Empty, method)]
+            fn parse(s: &str) -> u32 {
+                s.len() as u32
+            }
+        }
+
+        assert_eq!(parse("42"), 42);
+        assert!(matches!(parse_checked("42"), Ok(42)));
+        assert!(matches!(parse_checked(""), Err(OneOf2::First(Empty))));
+        assert!(matches!(
+            parse_checked("4x"),
+            Err(OneOf2::Second(Invalid('x')))
+        ));
+        // exceptions extending a declared one are declared too
+        let e = catch_is_a::<Empty, _>(|| parse_checked(" ")).unwrap_err();
+        assert!(e.is::<Blank>());
+
+        let e = catch_only::<UndeclaredException, _>(|| parse_checked("?")).unwrap_err();
+        assert!(e.function().ends_with("::parse"));
+        assert!(e.exception().is::<Unexpected>());
+        assert!((&e as &dyn Exception).cause().unwrap().is::<Unexpected>());
+
+        // the checked version calls the function, the body exists once
+        #[throws(Empty)]
+        fn counted() -> usize {
+            static CALLS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            CALLS.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1
+        }
+        assert_eq!(counted(), 1);
+        assert!(matches!(counted_checked(), Ok(2)));
+
+        #[throws(Invalid)]
+        fn sum<T: Into<u32>>((a, b): (T, T), mut rest: Vec<T>) -> u32 {
+            rest.push(a);
+            rest.push(b);
+            rest.into_iter().map(Into::into).sum()
+        }
+        assert!(matches!(sum_checked((1u8, 2), vec![3]), Ok(6)));
+
+        let mut parser = Parser(0);
+        assert!(matches!(parser.digit_checked('7'), Ok(Ok(7))));
+        assert!(matches!(parser.digit_checked('-'), Ok(Err(7))));
+        assert!(matches!(parser.digit_checked('a'), Err(Invalid('a'))));
+        assert!(matches!(Parser::new_checked('b'), Err(Invalid('b'))));
+        assert_eq!(Parser::new('3').0, 3);
+        assert!(matches!(Parser::parse_checked("abc"), Ok(3)));
+    }
+
+    #[test]
+    fn typed() {
+        #[derive(Debug, Exception)]
+        struct Empty;
+        #[derive(Debug, Exception)]
+        struct Invalid(char);
+        #[derive(Debug, Exception)]
+        struct Other;
+
+        fn digit(c: char, invalid: Can<Invalid>) -> u32 {
+            c.to_digit(10).unwrap_or_else(|| invalid.throw(Invalid(c)))
+        }
+        fn parse(s: &str, empty: Can<Empty>, invalid: Can<Invalid>) -> u32 {
+            if s.is_empty() {
+                empty.throw(Empty);
+            }
+            s.chars().fold(0, |n, c| n * 10 + digit(c, invalid))
+        }
+
+        assert_eq!(std::mem::size_of::<Can<Empty>>(), 0);
+        assert!(matches!(catch_typed(|invalid| digit('4', invalid)), Ok(4)));
+        assert!(matches!(
+            catch_typed(|invalid| digit('x', invalid)),
+            Err(Invalid('x'))
+        ));
+        assert!(matches!(catch_typed2(|e, i| parse("42", e, i)), Ok(42)));
+        assert!(matches!(
+            catch_typed2(|e, i| parse("", e, i)),
+            Err(OneOf2::First(Empty))
+        ));
+        // nested handlers provide the capabilities separately
+        let r = catch_typed(|e| catch_typed(|i| parse("4y", e, i)));
+        assert!(matches!(r, Ok(Err(Invalid('y')))));
+        assert!(matches!(
+            catch_typed3(|_: Can<Empty>, _: Can<Invalid>, other| other.throw(Other)),
+            Err(OneOf3::Third(Other))
+        ));
+
+        let r = catch(|| catch_typed::<Empty, _>(|_| throw(Other)));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Other>()));
+    }
+
+    #[test]
+    fn rethrow() {
+        #[derive(Debug, Exception)]
+        struct Deep(u32);
+
+        fn level1() {
+            throw(Deep(1))
+        }
+        fn level2() {
+            if let Err(e) = catch(level1) {
+                e.rethrow()
+            }
+        }
+        fn level3() {
+            match catch(level2) {
+                Err(CatchError::Exception(e)) => super::rethrow(e),
+                Err(CatchError::Panic(p)) => panic::resume_unwind(p.into_payload()),
+                Ok(()) => {}
+            }
+        }
+
+        let line = line!() - 15;
+        let e = catch_only::<Deep, _>(level3).unwrap_err();
+        assert!(matches!(e, Deep(1)));
+        let e = catch_is_a::<Deep, _>(level3).unwrap_err();
+        assert_eq!(e.throw_info().unwrap().location().line(), line);
+
+        // throwing the caught box again still downcasts to the concrete type
+        let e = catch(|| match catch(level1) {
+            Err(CatchError::Exception(e)) => throw(e),
+            _ => unreachable!(),
+        });
+        match e {
+            Err(CatchError::Exception(e)) => {
+                assert_eq!(e.name(), "Deep");
+                assert!(e.is::<Deep>());
+                assert!(matches!(e.downcast::<Deep>(), Deep(1)));
+            }
+            _ => panic!("expected an exception"),
+        }
+
+        let line = line!() + 1;
+        let r = catch(|| catch(|| panic!("plain")).unwrap_err().rethrow());
+        let e = r.unwrap_err();
+        assert_eq!(e.panic_message(), Some("plain"));
+        assert_eq!(e.panic_location().unwrap().line(), line);
+
+        // a swallowed panic doesn't lend its location to a payload resumed afterwards
+        let r = catch(|| {
+            let _ = std::panic::catch_unwind(|| panic!("swallowed"));
+            std::panic::resume_unwind(Box::new("resumed"))
+        });
+        let e = r.unwrap_err();
+        assert_eq!(e.panic_message(), Some("resumed"));
+        assert!(e.panic_location().is_none());
+        let r = catch(|| {
+            let _ = std::panic::catch_unwind(|| panic!("swallowed"));
+        });
+        assert!(r.is_ok());
+        let r = catch(|| std::panic::resume_unwind(Box::new("swallowed")));
+        assert!(r.unwrap_err().panic_location().is_none());
+    }
+
+    #[test]
+    fn uniform() {
+        #[derive(Debug, Exception)]
+        struct A;
+
+        let line = line!() + 1;
+        let e = catch_uniform(|| "".chars().next().unwrap()).unwrap_err();
+        assert_eq!(e.name(), "PanicException");
+        assert!(e.is_a::<PanicException>());
+        let message = e.message();
+        let panic = e.downcast::<PanicException>();
+        assert!(panic.panic_message().unwrap().contains("`None`"));
+        assert_eq!(panic.location().unwrap().line(), line);
+        assert_eq!(
+            panic.message(),
+            Some(format!(
+                "{} at {}",
+                panic.panic_message().unwrap(),
+                panic.location().unwrap()
+            ))
+        );
+        assert_eq!(panic.message(), message);
+        assert_eq!(panic.location().unwrap().file(), file!());
+
+        let e = catch_uniform(|| std::panic::panic_any(1)).unwrap_err();
+        assert!(e.is::<PanicException>());
+        assert_eq!(e.to_string().split(" at ").next(), Some("panic"));
+
+        let e = catch_uniform(|| throw(A)).unwrap_err();
+        assert!(e.is::<A>());
+        assert!(matches!(catch_uniform(|| 1), Ok(1)));
+    }
+
+    #[test]
+    fn guards() {
+        use std::cell::RefCell;
+        use std::panic::AssertUnwindSafe;
+
+        #[derive(Debug, Exception)]
+        struct Conflict(u32);
+        #[derive(Debug, Exception)]
+        struct Timeout;
+
+        let log = RefCell::new(Vec::new());
+        let transaction = |e: Option<Box<dyn Fn()>>| {
+            defer! { log.borrow_mut().push("close".to_string()) }
+            let _rollback = on_exception(|c: &Conflict| {
+                log.borrow_mut().push(format!("rollback {}", c.0));
+            });
+            let _unwind = on_unwind(|e| {
+                let name = e.map_or("panic", |e| e.name());
+                log.borrow_mut().push(format!("unwind {}", name));
+            });
+            if let Some(e) = e {
+                e()
+            }
+            log.borrow_mut().push("commit".to_string());
+        };
+        let take = || log.borrow_mut().drain(..).collect::<Vec<_>>();
+
+        transaction(None);
+        assert_eq!(take(), ["commit", "close"]);
+
+        let r = catch(AssertUnwindSafe(|| {
+            transaction(Some(Box::new(|| throw(Conflict(1)))))
+        }));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Conflict>()));
+        assert_eq!(take(), ["unwind Conflict", "rollback 1", "close"]);
+
+        let r = catch(AssertUnwindSafe(|| {
+            transaction(Some(Box::new(|| throw(Timeout))))
+        }));
+        assert!(r.is_err());
+        assert_eq!(take(), ["unwind Timeout", "close"]);
+
+        let r = catch(AssertUnwindSafe(|| {
+            transaction(Some(Box::new(|| panic!("this is an intended test panic"))))
+        }));
+        assert!(matches!(r, Err(CatchError::Panic(_))));
+        assert_eq!(take(), ["unwind panic", "close"]);
+
+        // rethrown exceptions are still visible to the guards above the inner catch
+        let r = catch(AssertUnwindSafe(|| {
+            let _outer = on_exception(|c: &Conflict| {
+                log.borrow_mut().push(format!("outer {}", c.0));
+            });
+            let _ = catch_only::<Timeout, _>(AssertUnwindSafe(|| {
+                transaction(Some(Box::new(|| throw(Conflict(2)))))
+            }));
+        }));
+        assert!(r.is_err());
+        assert_eq!(
+            take(),
+            ["unwind Conflict", "rollback 2", "close", "outer 2"]
+        );
+
+        // guards dropped by another guard don't block on the exception, they don't see it
+        let r = catch(AssertUnwindSafe(|| {
+            let _outer = on_exception(|c: &Conflict| {
+                let _nested = on_unwind(|e| {
+                    log.borrow_mut().push(format!("nested {}", e.is_some()));
+                });
+                log.borrow_mut().push(format!("outer {}", c.0));
+            });
+            throw(Conflict(4))
+        }));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Conflict>()));
+        assert_eq!(take(), ["outer 4", "nested false"]);
+        let r = catch(AssertUnwindSafe(|| {
+            let _outer = on_unwind(|_| {
+                drop(on_exception(|_: &Conflict| unreachable!()));
+                log.borrow_mut().push("outer".to_string());
+            });
+            throw(Conflict(5))
+        }));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Conflict>()));
+        assert_eq!(take(), ["outer"]);
+
+        // exceptions swallowed without catch are not seen by the guards of later panics
+        let unwound = |swallow: &dyn Fn()| {
+            let r = catch(AssertUnwindSafe(|| {
+                swallow();
+                let _unwind = on_unwind(|e| {
+                    log.borrow_mut()
+                        .push(e.map_or("panic", |e| e.name()).to_string());
+                });
+                panic!("this is an intended test panic")
+            }));
+            assert!(matches!(r, Err(CatchError::Panic(_))));
+            take()
+        };
+        assert_eq!(
+            unwound(&|| drop(panic::catch_unwind(|| throw(Conflict(6))))),
+            ["panic"]
+        );
+        let kept = RefCell::new(None);
+        let swallow = || *kept.borrow_mut() = panic::catch_unwind(|| throw(Conflict(7))).err();
+        assert_eq!(unwound(&swallow), ["panic"]);
+        drop(kept);
+        #[throws(Timeout)]
+        fn undeclared() {
+            throw(Conflict(8))
+        }
+        assert_eq!(
+            unwound(&|| drop(panic::catch_unwind(undeclared))),
+            ["panic"]
+        );
+
+        // nor by the guards of a payload resumed without going through the hook
+        let kept = panic::catch_unwind(|| throw(Conflict(9))).err();
+        let r = catch(AssertUnwindSafe(|| {
+            let _unwind = on_unwind(|e| {
+                log.borrow_mut()
+                    .push(e.map_or("panic", |e| e.name()).to_string());
+            });
+            panic::resume_unwind(Box::new(1u8))
+        }));
+        assert!(matches!(r, Err(CatchError::Panic(_))));
+        assert_eq!(take(), ["panic"]);
+        let r = catch(AssertUnwindSafe(|| {
+            let _rollback = on_exception(|_: &Conflict| unreachable!());
+            let kept = panic::catch_unwind(|| throw(Conflict(10))).err();
+            let _unwind = on_unwind(|e| {
+                log.borrow_mut()
+                    .push(e.map_or("panic", |e| e.name()).to_string());
+            });
+            drop(kept);
+            panic::resume_unwind(Box::new(1u8))
+        }));
+        assert!(matches!(r, Err(CatchError::Panic(_))));
+        assert_eq!(take(), ["panic"]);
+        drop(kept);
+
+        // a guard that is not unwinding doesn't see the exception of a finished catch
+        let _ = catch(|| throw(Conflict(3)));
+        drop(on_exception(|_: &Conflict| unreachable!()));
+        let d = Defer::new(|| unreachable!());
+        d.cancel();
+    }
+
+    #[test]
+    fn as_dyn() {
+        trait Retryable {
+            fn attempts(&self) -> u32;
+        }
+        trait HasStatus {
+            fn status(&self) -> u16;
+        }
+        #[derive(Debug, Exception)]
+        #[exception(as_dyn(Retryable, HasStatus))]
+        struct Unavailable;
+        impl Retryable for Unavailable {
+            fn attempts(&self) -> u32 {
+                3
+            }
+        }
+        impl HasStatus for Unavailable {
+            fn status(&self) -> u16 {
+                503
+            }
+        }
+        #[derive(Debug, Exception)]
+        #[exception(as_dyn(HasStatus))]
+        enum Client {
+            NotFound,
+        }
+        impl HasStatus for Client {
+            fn status(&self) -> u16 {
+                404
+            }
+        }
+
+        let e = catch_dyn::<dyn Retryable, _>(|| throw(Unavailable)).unwrap_err();
+        assert_eq!(e.as_dyn::<dyn Retryable>().unwrap().attempts(), 3);
+        assert_eq!(e.as_dyn::<dyn HasStatus>().unwrap().status(), 503);
+        assert!(e.as_dyn::<dyn fmt::Display>().is_none());
+
+        let e = catch_dyn::<dyn HasStatus, _>(|| throw(Client::NotFound)).unwrap_err();
+        assert_eq!(e.as_dyn::<dyn HasStatus>().unwrap().status(), 404);
+        assert!(e.as_dyn::<dyn Retryable>().is_none());
+
+        let r = catch(|| catch_dyn::<dyn Retryable, _>(|| throw(Client::NotFound)));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Client>()));
+    }
+
+    #[test]
+    fn only() {
+        #[derive(Debug, Exception)]
+        struct A;
+        #[derive(Debug, Exception)]
+        struct B;
+
+        assert!(matches!(catch_only::<A, _>(|| throw(A)), Err(A)));
+        assert!(matches!(catch_only::<A, _>(|| 1), Ok(1)));
+
+        // B is not handled by the inner catch, the outer one still sees it untouched
+        let r = catch(|| catch_only::<A, _>(|| throw(B)));
+        if let Err(CatchError::Exception(e)) = r {
+            assert!(matches!(e.downcast::<B>(), B));
+        } else {
+            panic!("test failed");
+        }
+
+        let r = catch(|| catch_only::<A, _>(|| panic!("this is an intended test panic")));
+        if let Err(CatchError::Panic(p)) = r {
+            assert_eq!(
+                p.downcast_ref::<&str>(),
+                Some(&"this is an intended test panic")
+            );
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn only_multi() {
+        #[derive(Debug, Exception)]
+        struct A;
+        #[derive(Debug, Exception)]
+        struct B;
+        #[derive(Debug, Exception)]
+        struct C;
+
+        assert!(matches!(
+            catch_only2::<A, B, _>(|| throw(B)),
+            Err(OneOf2::Second(B))
+        ));
+        assert!(matches!(
+            catch_only3::<A, B, C, _>(|| throw(C)),
+            Err(OneOf3::Third(C))
+        ));
+
+        let r = catch(|| catch_only2::<A, B, _>(|| throw(C)));
+        if let Err(CatchError::Exception(e)) = r {
+            assert!(matches!(e.downcast::<C>(), C));
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn try_catch_arms() {
+        #[derive(Debug, Exception)]
+        struct A(u8);
+        #[derive(Debug, Exception)]
+        struct B(u8);
+        #[derive(Debug, Exception)]
+        struct C;
+
+        let handle = |f: fn() -> u8| {
+            try_catch! {
+                try {
+                    f()
+                } catch (a: A) {
+                    a.0
+                } catch (b: B) {
+                    b.0 * 10
+                } catch panic(_p) {
+                    100
+                }
+            }
+        };
+        assert_eq!(handle(|| 1), 1);
+        assert_eq!(handle(|| throw(A(2))), 2);
+        assert_eq!(handle(|| throw(B(3))), 30);
+        assert_eq!(handle(|| panic!("this is an intended test panic")), 100);
+
+        // C has no arm, it propagates untouched
+        let r = catch(|| handle(|| throw(C)));
+        if let Err(CatchError::Exception(e)) = r {
+            assert!(matches!(e.downcast::<C>(), C));
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn try_catch_else_finally() {
+        #[derive(Debug, Exception)]
+        struct A;
+        #[derive(Debug, Exception)]
+        struct B;
+
+        let mut log = vec![];
+        let r = try_catch! {
+            try {
+                log.push("try");
+                1
+            } catch (_a: A) {
+                log.push("catch");
+                2
+            } else {
+                log.push("else");
+            } finally {
+                log.push("finally");
+            }
+        };
+        assert_eq!(r, 1);
+        assert_eq!(log, ["try", "else", "finally"]);
+
+        // finally runs even if a catch arm throws, and the new exception propagates
+        let mut finally = false;
+        let r = catch(panic::AssertUnwindSafe(|| {
+            try_catch! {
+                try {
+                    throw(A)
+                } catch (_a: A) {
+                    throw(B)
+                } finally {
+                    finally = true;
+                }
+            }
+        }));
+        assert!(finally);
+        if let Err(CatchError::Exception(e)) = r {
+            assert!(matches!(e.downcast::<B>(), B));
+        } else {
+            panic!("test failed");
+        }
+
+        // unhandled panics propagate after finally
+        let mut finally = false;
+        let r = catch(panic::AssertUnwindSafe(|| {
+            try_catch! {
+                try {
+                    panic!("this is an intended test panic")
+                } catch (_a: A) {
+                } finally {
+                    finally = true;
+                }
+            }
+        }));
+        assert!(finally);
+        assert!(matches!(r, Err(CatchError::Panic(_))));
+    }
 }
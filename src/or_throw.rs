@@ -0,0 +1,154 @@
+//! Bridge between `Result`/`Option` based code and exceptions.
+
+use crate::{throw, Exception};
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+
+/// Extension trait that throws the error of a `Result`, or the missing value of an `Option`.\
+/// The location recorded by [throw] is the call site of these methods.
+pub trait OrThrow<T, E> {
+    /// Return the value or throw the error wrapped in an [ErrorException]
+    fn or_throw(self) -> T
+    where
+        E: Error + Send + 'static;
+    /// Return the value or throw the exception built from the error
+    fn or_throw_with<X: Exception>(self, f: impl FnOnce(E) -> X) -> T;
+    /// Return the value or throw the given exception
+    fn expect_or_throw(self, exception: impl Exception) -> T;
+}
+
+impl<T, E> OrThrow<T, E> for Result<T, E> {
+    #[track_caller]
+    fn or_throw(self) -> T
+    where
+        E: Error + Send + 'static,
+    {
+        match self {
+            Ok(value) => value,
+            Err(error) => throw(ErrorException(error)),
+        }
+    }
+    #[track_caller]
+    fn or_throw_with<X: Exception>(self, f: impl FnOnce(E) -> X) -> T {
+        match self {
+            Ok(value) => value,
+            Err(error) => throw(f(error)),
+        }
+    }
+    #[track_caller]
+    fn expect_or_throw(self, exception: impl Exception) -> T {
+        match self {
+            Ok(value) => value,
+            Err(_) => throw(exception),
+        }
+    }
+}
+
+impl<T> OrThrow<T, NoneError> for Option<T> {
+    #[track_caller]
+    fn or_throw(self) -> T {
+        match self {
+            Some(value) => value,
+            None => throw(ErrorException(NoneError)),
+        }
+    }
+    #[track_caller]
+    fn or_throw_with<X: Exception>(self, f: impl FnOnce(NoneError) -> X) -> T {
+        match self {
+            Some(value) => value,
+            None => throw(f(NoneError)),
+        }
+    }
+    #[track_caller]
+    fn expect_or_throw(self, exception: impl Exception) -> T {
+        match self {
+            Some(value) => value,
+            None => throw(exception),
+        }
+    }
+}
+
+/// The error of an `Option` that has no value, see [OrThrow]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoneError;
+
+impl fmt::Display for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("called `or_throw` on a `None` value")
+    }
+}
+
+impl Error for NoneError {}
+
+/// Exception that wraps a standard error, thrown by [OrThrow::or_throw]
+#[derive(Debug)]
+pub struct ErrorException<E>(pub E);
+
+impl<E: Error + Send + 'static> Exception for ErrorException<E> {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn message(&self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{catch, CatchError, ExceptionDowncast};
+    use std::num::ParseIntError;
+
+    #[test]
+    fn result() {
+        assert_eq!("1".parse::<u8>().or_throw(), 1);
+
+        let line = line!() + 1;
+        let r = catch(|| "x".parse::<u8>().or_throw());
+        if let Err(CatchError::Exception(e)) = r {
+            assert_eq!(e.name(), "ErrorException");
+            assert_eq!(e.to_string(), "invalid digit found in string");
+            assert_eq!(e.throw_info().unwrap().location().line(), line);
+            assert!(e.try_downcast::<ErrorException<ParseIntError>>().is_ok());
+        } else {
+            panic!("test failed");
+        }
+    }
+
+    #[test]
+    fn with() {
+        #[derive(Debug, crate::Exception)]
+        struct Invalid(String);
+        #[derive(Debug, crate::Exception)]
+        struct Missing;
+
+        let r = catch(|| "x".parse::<u8>().or_throw_with(|e| Invalid(e.to_string())));
+        if let Err(CatchError::Exception(e)) = r {
+            assert_eq!(e.downcast::<Invalid>().0, "invalid digit found in string");
+        } else {
+            panic!("test failed");
+        }
+        let r = catch(|| None::<u8>.expect_or_throw(Missing));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.name() == "Missing"));
+        let r = catch(|| None::<u8>.or_throw());
+        if let Err(CatchError::Exception(e)) = r {
+            assert!(matches!(
+                e.downcast::<ErrorException<NoneError>>(),
+                ErrorException(NoneError)
+            ));
+        } else {
+            panic!("test failed");
+        }
+        assert_eq!(Some(2).or_throw(), 2);
+    }
+}
@@ -0,0 +1,223 @@
+//! Threads whose exceptions are returned to the joining thread.
+//!
+//! Mirrors [std::thread], the spawned closures run inside [catch] so joining returns a
+//! [CatchError] instead of an untyped panic payload.
+
+use crate::{catch, CatchError, CaughtPanic};
+use std::io;
+use std::panic::AssertUnwindSafe;
+use std::thread::{self, Thread};
+
+/// Spawn a thread running `f` inside [catch], see [std::thread::spawn]
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    JoinHandle(thread::spawn(move || catch(AssertUnwindSafe(f))))
+}
+
+/// Create a scope for spawning threads that borrow local variables, see [std::thread::scope].\
+/// Errors of threads that are not joined explicitly are discarded.
+pub fn scope<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(Scope<'scope, 'env>) -> T,
+{
+    thread::scope(|scope| f(Scope(scope)))
+}
+
+/// Thread configuration, see [std::thread::Builder]
+#[derive(Debug)]
+pub struct Builder(thread::Builder);
+
+impl Builder {
+    /// Base configuration for spawning a thread
+    pub fn new() -> Self {
+        Builder(thread::Builder::new())
+    }
+    /// Name the thread, the name is recorded in the [ThrowInfo](crate::ThrowInfo) of its
+    /// exceptions
+    pub fn name(self, name: String) -> Self {
+        Builder(self.0.name(name))
+    }
+    /// Set the stack size of the thread
+    pub fn stack_size(self, size: usize) -> Self {
+        Builder(self.0.stack_size(size))
+    }
+    /// Spawn a thread running `f` inside [catch]
+    pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.0
+            .spawn(move || catch(AssertUnwindSafe(f)))
+            .map(JoinHandle)
+    }
+    /// Spawn a scoped thread running `f` inside [catch]
+    pub fn spawn_scoped<'scope, 'env, F, T>(
+        self,
+        scope: Scope<'scope, 'env>,
+        f: F,
+    ) -> io::Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.0
+            .spawn_scoped(scope.0, move || catch(AssertUnwindSafe(f)))
+            .map(ScopedJoinHandle)
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder::new()
+    }
+}
+
+/// Owned permission to join a thread spawned by [spawn]
+#[derive(Debug)]
+pub struct JoinHandle<T>(thread::JoinHandle<Result<T, CatchError>>);
+
+impl<T> JoinHandle<T> {
+    /// Wait for the thread to finish, its exception or panic is returned as an error
+    pub fn join(self) -> Result<T, CatchError> {
+        self.0
+            .join()
+            .unwrap_or_else(|p| Err(CatchError::Panic(CaughtPanic::new(p, None))))
+    }
+    /// Wait for the thread to finish and rethrow its exception or panic in the current thread.\
+    /// The [ThrowInfo](crate::ThrowInfo) of a rethrown exception still names the worker thread.
+    pub fn join_rethrow(self) -> T {
+        self.join().unwrap_or_else(|e| e.rethrow())
+    }
+    /// The thread
+    pub fn thread(&self) -> &Thread {
+        self.0.thread()
+    }
+    /// Check if the thread finished running
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+}
+
+/// Scope to spawn threads in, see [scope]
+#[derive(Debug, Clone, Copy)]
+pub struct Scope<'scope, 'env: 'scope>(&'scope thread::Scope<'scope, 'env>);
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Spawn a scoped thread running `f` inside [catch]
+    pub fn spawn<F, T>(self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        ScopedJoinHandle(self.0.spawn(move || catch(AssertUnwindSafe(f))))
+    }
+}
+
+/// Owned permission to join a thread spawned by [Scope::spawn]
+#[derive(Debug)]
+pub struct ScopedJoinHandle<'scope, T>(thread::ScopedJoinHandle<'scope, Result<T, CatchError>>);
+
+impl<T> ScopedJoinHandle<'_, T> {
+    /// Wait for the thread to finish, its exception or panic is returned as an error
+    pub fn join(self) -> Result<T, CatchError> {
+        self.0
+            .join()
+            .unwrap_or_else(|p| Err(CatchError::Panic(CaughtPanic::new(p, None))))
+    }
+    /// Wait for the thread to finish and rethrow its exception or panic in the current thread.\
+    /// The [ThrowInfo](crate::ThrowInfo) of a rethrown exception still names the worker thread.
+    pub fn join_rethrow(self) -> T {
+        self.join().unwrap_or_else(|e| e.rethrow())
+    }
+    /// The thread
+    pub fn thread(&self) -> &Thread {
+        self.0.thread()
+    }
+    /// Check if the thread finished running
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{throw, Exception, ExceptionDowncast};
+
+    #[derive(Debug, Exception)]
+    struct Failed(u32);
+
+    #[test]
+    fn join() {
+        assert_eq!(spawn(|| 1).join().unwrap(), 1);
+        match spawn(|| throw(Failed(2))).join() {
+            Err(CatchError::Exception(e)) => assert!(matches!(e.downcast::<Failed>(), Failed(2))),
+            _ => panic!("expected an exception"),
+        }
+        assert!(matches!(
+            spawn(|| panic!("boom")).join(),
+            Err(CatchError::Panic(_))
+        ));
+
+        let worker = Builder::new()
+            .name("worker".into())
+            .spawn(|| throw(Failed(3)))
+            .unwrap();
+        let r = catch(AssertUnwindSafe(|| worker.join_rethrow()));
+        match r {
+            Err(CatchError::Exception(e)) => {
+                let info = e.throw_info().unwrap();
+                assert_eq!(info.thread_name(), Some("worker"));
+                assert!(matches!(e.downcast::<Failed>(), Failed(3)));
+            }
+            _ => panic!("expected an exception"),
+        }
+    }
+
+    #[test]
+    fn scoped() {
+        let ids = [1, 2, 3];
+        let sum = scope(|s| {
+            let handles: Vec<_> = ids
+                .iter()
+                .map(|&id| {
+                    s.spawn(move || {
+                        if id == 2 {
+                            throw(Failed(id));
+                        }
+                        id
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| match h.join() {
+                    Ok(id) => id,
+                    Err(CatchError::Exception(e)) => e.downcast::<Failed>().0 * 10,
+                    Err(CatchError::Panic(_)) => panic!("unexpected panic"),
+                })
+                .sum::<u32>()
+        });
+        assert_eq!(sum, 24);
+
+        let r = catch(|| {
+            scope(|s| {
+                Builder::new()
+                    .name("scoped".into())
+                    .spawn_scoped(s, || throw(Failed(ids[0])))
+                    .unwrap()
+                    .join_rethrow()
+            })
+        });
+        match r {
+            Err(CatchError::Exception(e)) => {
+                assert_eq!(e.throw_info().unwrap().thread_name(), Some("scoped"))
+            }
+            _ => panic!("expected an exception"),
+        }
+    }
+}
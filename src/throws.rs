@@ -0,0 +1,114 @@
+//! Support for the [throws](crate::throws) attribute.
+
+use crate::{rethrow, throw, Exception};
+use std::any::{Any, TypeId};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// 0: report in debug builds only, 1: always report, 2: never report
+static REPORT_UNDECLARED: AtomicU8 = AtomicU8::new(0);
+
+/// Enable or disable the report printed to stderr when an exception escapes a
+/// [throws](crate::throws) function without being declared.\
+/// By default undeclared exceptions are reported in debug builds only, they are converted into
+/// an [UndeclaredException] either way.
+pub fn set_report_undeclared(enabled: bool) {
+    REPORT_UNDECLARED.store(if enabled { 1 } else { 2 }, Ordering::Relaxed);
+}
+
+fn report_undeclared() -> bool {
+    match REPORT_UNDECLARED.load(Ordering::Relaxed) {
+        0 => cfg!(debug_assertions),
+        report => report == 1,
+    }
+}
+
+/// Thrown when an exception escapes a function annotated with [throws](crate::throws) without
+/// being declared by it.\
+/// The undeclared exception is kept as the [cause](Exception::cause).
+#[derive(Debug)]
+pub struct UndeclaredException {
+    function: &'static str,
+    exception: Box<dyn Exception>,
+}
+
+impl UndeclaredException {
+    /// Path of the function the exception escaped from
+    pub fn function(&self) -> &'static str {
+        self.function
+    }
+    /// The undeclared exception
+    pub fn exception(&self) -> &dyn Exception {
+        &*self.exception
+    }
+    /// Take the undeclared exception
+    pub fn into_exception(self) -> Box<dyn Exception> {
+        self.exception
+    }
+}
+
+impl fmt::Display for UndeclaredException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` threw the undeclared exception {}",
+            self.function,
+            self.exception.name()
+        )
+    }
+}
+
+impl Exception for UndeclaredException {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn cause(&self) -> Option<&dyn Exception> {
+        Some(&*self.exception)
+    }
+    fn message(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+/// Run the body of `function`, exceptions that are not one of the `declared` types (or extend
+/// one of them) are replaced by an [UndeclaredException], see [set_report_undeclared].\
+/// Unlike `catch` the hook is not silenced, the original exception is still reported where it
+/// was thrown.
+#[track_caller]
+pub fn declared<T>(function: &'static str, declared: &[TypeId], body: impl FnOnce() -> T) -> T {
+    let payload = match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => return value,
+        Err(payload) => payload,
+    };
+    match payload.downcast::<Box<dyn Exception>>() {
+        Ok(exception) => {
+            let exception = *exception;
+            let id = exception.exception_type_id();
+            if declared
+                .iter()
+                .any(|&parent| id == parent || exception.extends(parent))
+            {
+                rethrow(exception)
+            }
+            let undeclared = UndeclaredException {
+                function,
+                exception,
+            };
+            if report_undeclared() {
+                match undeclared.exception.throw_info() {
+                    Some(info) => eprintln!("{} at {}", undeclared, info.location()),
+                    None => eprintln!("{}", undeclared),
+                }
+            }
+            throw(undeclared)
+        }
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
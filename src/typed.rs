@@ -0,0 +1,110 @@
+//! Compile time checked exceptions with capability tokens.
+//!
+//! Functions that may throw `E` take a [Can]`<E>` argument, the only way to get one is inside
+//! [catch_typed], so a call without an enclosing handler for `E` doesn't compile.
+
+use crate::{catch_only, catch_only2, catch_only3, throw, Exception, OneOf2, OneOf3};
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic::UnwindSafe;
+
+/// Capability to throw exceptions of type `E`, only available inside [catch_typed].\
+/// It is zero sized and `Copy`, the `'scope` lifetime keeps it from escaping its handler and it
+/// is neither `Send` nor `Sync` so it can't be used on another thread.
+pub struct Can<'scope, E> {
+    // Invariant so the token can't be converted to another scope
+    _scope: PhantomData<fn(&'scope ()) -> &'scope ()>,
+    _exception: PhantomData<fn() -> E>,
+    // Exceptions thrown on another thread don't unwind into the handler
+    _thread: PhantomData<*const ()>,
+}
+
+impl<'scope, E> Can<'scope, E> {
+    fn new() -> Self {
+        Can {
+            _scope: PhantomData,
+            _exception: PhantomData,
+            _thread: PhantomData,
+        }
+    }
+}
+
+impl<E: Exception> Can<'_, E> {
+    /// Throw `e`, it is caught by the enclosing [catch_typed]
+    #[track_caller]
+    pub fn throw(self, e: E) -> ! {
+        throw(e)
+    }
+}
+
+impl<E> Clone for Can<'_, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for Can<'_, E> {}
+
+impl<E> fmt::Debug for Can<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Can<{}>", std::any::type_name::<E>())
+    }
+}
+
+/// Runs a function with the capability to throw `E` and catch exceptions of type `E`.\
+/// Other exceptions and normal panics keep unwinding with their original payload.
+///
+/// ```rust
+///    use trycatch::{catch_typed, Can, Exception};
+///
+///    #[derive(Debug, Exception)]
+///    struct Empty;
+///
+///    fn first(s: &str, can: Can<Empty>) -> char {
+///        s.chars().next().unwrap_or_else(|| can.throw(Empty))
+///    }
+///
+///    assert_eq!(catch_typed(|can| first("abc", can)).unwrap(), 'a');
+///    assert!(catch_typed(|can| first("", can)).is_err());
+/// ```
+///
+/// Calling `first` without a handler doesn't compile, neither does keeping the token.
+///
+/// ```compile_fail
+///    # use trycatch::{catch_typed, Can, Exception};
+///    # #[derive(Debug, Exception)]
+///    # struct Empty;
+///    let can = catch_typed::<Empty, _>(|can| can).unwrap();
+/// ```
+///
+/// The token can't be moved to another thread either, the handler wouldn't see its exceptions.
+///
+/// ```compile_fail
+///    # use trycatch::{catch_typed, Can, Exception};
+///    # #[derive(Debug, Exception)]
+///    # struct Empty;
+///    let _ = catch_typed::<Empty, _>(|can| {
+///        std::thread::scope(|s| {
+///            s.spawn(move || can.throw(Empty));
+///        })
+///    });
+/// ```
+pub fn catch_typed<E: Exception, T>(
+    expr: impl for<'scope> FnOnce(Can<'scope, E>) -> T + UnwindSafe,
+) -> Result<T, E> {
+    catch_only(|| expr(Can::new()))
+}
+
+/// Same as [catch_typed] with the capabilities to throw `A` or `B`
+pub fn catch_typed2<A: Exception, B: Exception, T>(
+    expr: impl for<'scope> FnOnce(Can<'scope, A>, Can<'scope, B>) -> T + UnwindSafe,
+) -> Result<T, OneOf2<A, B>> {
+    catch_only2(|| expr(Can::new(), Can::new()))
+}
+
+/// Same as [catch_typed] with the capabilities to throw `A`, `B` or `C`
+pub fn catch_typed3<A: Exception, B: Exception, C: Exception, T>(
+    expr: impl for<'scope> FnOnce(Can<'scope, A>, Can<'scope, B>, Can<'scope, C>) -> T + UnwindSafe,
+) -> Result<T, OneOf3<A, B, C>> {
+    catch_only3(|| expr(Can::new(), Can::new(), Can::new()))
+}
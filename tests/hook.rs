@@ -0,0 +1,72 @@
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use trycatch::{catch, catch_exceptions, throw, CatchError, Exception, ExceptionDowncast};
+
+#[derive(Debug, Exception)]
+struct Depth(usize);
+
+static HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+// Each level catches the exception thrown by the level below it and rethrows it one deeper.
+fn nested(depth: usize) {
+    if depth == 0 {
+        throw(Depth(0));
+    }
+    match catch(|| nested(depth - 1)) {
+        Err(CatchError::Exception(e)) => throw(Depth(e.downcast::<Depth>().0 + 1)),
+        _ => panic!("test failed"),
+    }
+}
+
+#[test]
+fn concurrent_nested_catches() {
+    panic::set_hook(Box::new(|_| {
+        HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let catching: Vec<_> = (0..16)
+        .map(|t| {
+            thread::spawn(move || {
+                for i in 0..200 {
+                    let depth = (t + i) % 5;
+                    match catch(|| nested(depth)) {
+                        Err(CatchError::Exception(e)) => {
+                            assert_eq!(e.downcast::<Depth>().0, depth)
+                        }
+                        _ => panic!("test failed"),
+                    }
+                    // Normal panics inside `catch` are still reported by the previous hook
+                    assert!(matches!(
+                        catch(|| panic!("intended test panic")),
+                        Err(CatchError::Panic(_))
+                    ));
+                }
+            })
+        })
+        .collect();
+    // Panics outside of `catch` keep reaching the previous hook while other threads catch
+    let panicking: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                for _ in 0..50 {
+                    assert!(panic::catch_unwind(|| panic!("intended test panic")).is_err());
+                }
+            })
+        })
+        .collect();
+    for t in catching.into_iter().chain(panicking) {
+        t.join().unwrap();
+    }
+    assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 16 * 200 + 4 * 50);
+
+    // Once every catch is done the previous hook is back in charge, exceptions included
+    assert!(panic::catch_unwind(|| throw(Depth(0))).is_err());
+    assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 16 * 200 + 4 * 50 + 1);
+
+    // Panics resumed by catch_exceptions reach the previous hook once, like any other panic
+    let r = catch(|| catch_exceptions(|| panic!("intended test panic")));
+    assert_eq!(r.unwrap_err().panic_message(), Some("intended test panic"));
+    assert!(catch_exceptions(|| throw(Depth(1))).is_err());
+    assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 16 * 200 + 4 * 50 + 2);
+}
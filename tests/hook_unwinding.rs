@@ -0,0 +1,35 @@
+use std::panic;
+use trycatch::{catch, throw, CatchError, Exception};
+
+#[derive(Debug, Exception)]
+struct Cleanup;
+
+struct CatchOnDrop;
+
+impl Drop for CatchOnDrop {
+    fn drop(&mut self) {
+        // The first catch of the process runs while the thread is panicking
+        let r = catch(|| throw(Cleanup));
+        assert!(matches!(r, Err(CatchError::Exception(e)) if e.is::<Cleanup>()));
+    }
+}
+
+#[test]
+fn first_catch_while_unwinding() {
+    let r = panic::catch_unwind(|| {
+        let _catch = CatchOnDrop;
+        panic!("this is an intended test panic")
+    });
+    assert!(r.is_err());
+
+    // the hook is installed by the next call
+    let r = catch(|| panic!("this is an intended test panic"));
+    assert_eq!(
+        r.unwrap_err().panic_message(),
+        Some("this is an intended test panic")
+    );
+    assert!(matches!(
+        catch(|| throw(Cleanup)),
+        Err(CatchError::Exception(_))
+    ));
+}
@@ -0,0 +1,35 @@
+// `set_capture_backtrace` is process wide, this test has its own binary so it doesn't change
+// what the other tests capture.
+use trycatch::{catch, set_capture_backtrace, throw, CatchError, Exception, ExceptionDowncast};
+
+#[derive(Debug, Exception)]
+struct E;
+
+#[test]
+fn throw_info() {
+    set_capture_backtrace(true);
+    let (line, r) = std::thread::Builder::new()
+        .name("thrower".into())
+        .spawn(|| (line!(), catch(|| throw(E))))
+        .unwrap()
+        .join()
+        .unwrap();
+    if let Err(CatchError::Exception(e)) = r {
+        let info = e.throw_info().unwrap();
+        assert_eq!(info.location().file(), file!());
+        assert_eq!(info.location().line(), line);
+        assert_eq!(info.thread_name(), Some("thrower"));
+        assert!(info.timestamp() <= std::time::SystemTime::now());
+        assert!(info.backtrace().is_some());
+        assert!(matches!(e.downcast::<E>(), E));
+    } else {
+        panic!("test failed");
+    }
+
+    set_capture_backtrace(false);
+    if let Err(CatchError::Exception(e)) = catch(|| throw(E)) {
+        assert!(e.throw_info().unwrap().backtrace().is_none());
+    } else {
+        panic!("test failed");
+    }
+}
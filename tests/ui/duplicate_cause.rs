@@ -0,0 +1,14 @@
+use trycatch::Exception;
+
+#[derive(Debug, Exception)]
+struct Inner;
+
+#[derive(Debug, Exception)]
+struct E {
+    #[cause]
+    first: Inner,
+    #[cause]
+    second: Inner,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use trycatch::ExceptionSet;
+
+#[derive(ExceptionSet)]
+struct Set;
+
+fn main() {}
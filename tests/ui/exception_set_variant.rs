@@ -0,0 +1,12 @@
+use trycatch::{Exception, ExceptionSet};
+
+#[derive(Debug, Exception)]
+struct A;
+
+#[derive(ExceptionSet)]
+enum Set {
+    A(A),
+    B { a: A },
+}
+
+fn main() {}
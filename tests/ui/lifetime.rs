@@ -0,0 +1,6 @@
+use trycatch::Exception;
+
+#[derive(Debug, Exception)]
+struct E<'a>(&'a str);
+
+fn main() {}
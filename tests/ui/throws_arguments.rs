@@ -0,0 +1,12 @@
+use trycatch::{throws, Exception};
+
+#[derive(Debug, Exception)]
+struct A;
+
+#[throws()]
+fn none() {}
+
+#[throws(A)]
+async fn asynchronous() {}
+
+fn main() {}
@@ -0,0 +1,26 @@
+use trycatch::{throws, Exception};
+
+#[derive(Debug, Exception)]
+struct A;
+
+struct Parser;
+
+impl Parser {
+    #[throws(A)]
+    fn new() -> Self {
+        Parser
+    }
+}
+
+trait Parse {
+    fn parse(&self) -> u32;
+}
+
+impl Parse for Parser {
+    #[throws(A)]
+    fn parse(&self) -> u32 {
+        1
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use trycatch::Exception;
+
+#[derive(Exception)]
+union E {
+    a: u8,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use trycatch::Exception;
+
+#[derive(Debug, Exception)]
+#[exception(eror)]
+struct E;
+
+fn main() {}
@@ -0,0 +1,9 @@
+use trycatch::Exception;
+
+#[derive(Debug, Exception)]
+#[exception(message = "file {path} not found")]
+struct NotFound {
+    file: String,
+}
+
+fn main() {}
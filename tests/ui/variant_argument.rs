@@ -0,0 +1,9 @@
+use trycatch::Exception;
+
+#[derive(Debug, Exception)]
+enum Net {
+    #[exception(name = "Timeout")]
+    Timeout,
+}
+
+fn main() {}
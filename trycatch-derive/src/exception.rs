@@ -0,0 +1,372 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parenthesized, Attribute, Data, DataEnum, DeriveInput, Error, Field, Fields, Ident, LitStr,
+    Member, Path, Token, Type,
+};
+
+/// Options given with `#[exception(..)]` on the type or on enum variants
+#[derive(Default)]
+struct Options {
+    error: bool,
+    name: Option<LitStr>,
+    message: Option<LitStr>,
+    parent: Option<Type>,
+    as_dyn: Vec<Path>,
+}
+
+impl Options {
+    /// Parse the `#[exception(..)]` attributes, only the `allowed` arguments are accepted
+    fn parse(attributes: &[Attribute], allowed: &[&str]) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for attribute in attributes {
+            if !attribute.path().is_ident("exception") {
+                continue;
+            }
+            attribute.parse_nested_meta(|meta| {
+                let argument = meta.path.get_ident().map(Ident::to_string);
+                match argument.as_deref() {
+                    Some(argument) if !allowed.contains(&argument) => {}
+                    Some("error") => {
+                        options.error = true;
+                        return Ok(());
+                    }
+                    Some("name") => {
+                        options.name = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    }
+                    Some("message") => {
+                        options.message = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    }
+                    Some("parent") => {
+                        options.parent = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    }
+                    Some("as_dyn") => {
+                        let traits;
+                        parenthesized!(traits in meta.input);
+                        let traits = Punctuated::<Path, Token![,]>::parse_terminated(&traits)?;
+                        options.as_dyn.extend(traits);
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+                let allowed: Vec<_> = allowed.iter().map(|a| format!("`{}`", a)).collect();
+                Err(meta.error(format!(
+                    "unknown exception argument, expected {}",
+                    allowed.join(", ")
+                )))
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let (options, cause, message) = match &input.data {
+        Data::Struct(data) => {
+            let options = Options::parse(
+                &input.attrs,
+                &["error", "name", "message", "parent", "as_dyn"],
+            )?;
+            let cause = struct_cause(&data.fields)?;
+            let message = match &options.message {
+                Some(template) => {
+                    let (pattern, format) = message_format(template, &data.fields)?;
+                    quote!(let Self #pattern = self; #format)
+                }
+                None => quote!(),
+            };
+            (options, cause, message)
+        }
+        Data::Enum(data) => {
+            let options = Options::parse(&input.attrs, &["error", "name", "parent", "as_dyn"])?;
+            (options, enum_cause(data)?, enum_message(data)?)
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "Exception can only be derived for structs and enums",
+            ))
+        }
+    };
+    let as_error = if options.error {
+        quote! {
+            fn as_error(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)> {
+                ::std::option::Option::Some(self)
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let parent = match &options.parent {
+        Some(parent) => quote! {
+            fn extends(&self, parent: ::std::any::TypeId) -> bool {
+                <Self as ::trycatch::Exception>::type_extends(parent)
+            }
+            fn type_extends(parent: ::std::any::TypeId) -> bool {
+                ::trycatch::__private::extends_parent::<#parent>(parent)
+            }
+        },
+        None => quote!(),
+    };
+    let as_dyn = if options.as_dyn.is_empty() {
+        quote!()
+    } else {
+        let traits = &options.as_dyn;
+        quote! {
+            fn provide_dyn<'a>(&'a self, request: &mut ::trycatch::DynRequest<'a>) {
+                #(request.provide::<dyn #traits>(self);)*
+            }
+        }
+    };
+    let ident = &input.ident;
+    let name = match options.name {
+        Some(name) => name.value(),
+        None => ident.to_string(),
+    };
+    let variants = match &input.data {
+        Data::Enum(data) => enum_variants(data, &name),
+        _ => quote!(),
+    };
+    let (message, display) = if message.is_empty() {
+        (quote!(), quote!())
+    } else {
+        let message = quote! {
+            #[allow(unreachable_patterns)]
+            fn message(&self) -> ::std::option::Option<::std::string::String> {
+                #message
+            }
+        };
+        let display = quote! {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match ::trycatch::Exception::message(self) {
+                    ::std::option::Option::Some(message) => f.write_str(&message),
+                    ::std::option::Option::None => f.write_str(::trycatch::Exception::name(self)),
+                }
+            }
+        };
+        (message, display)
+    };
+    let generics = crate::static_generics(input)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut where_clause = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    where_clause
+        .predicates
+        .push(syn::parse_quote!(#ident #ty_generics: ::std::fmt::Debug + ::std::marker::Send));
+
+    let exception = quote! {
+        impl #impl_generics ::trycatch::Exception for #ident #ty_generics #where_clause {
+            fn name(&self) -> &'static str {
+                #name
+            }
+            fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn ::std::any::Any> {
+                self
+            }
+            fn as_any(&self) -> &dyn ::std::any::Any {
+                self
+            }
+            fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+                self
+            }
+            #cause
+            #as_error
+            #message
+            #variants
+            #parent
+            #as_dyn
+        }
+    };
+    if display.is_empty() {
+        return Ok(exception);
+    }
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    Ok(quote! {
+        #exception
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+            #display
+        }
+    })
+}
+
+/// `cause` method returning the struct field marked with `#[cause]`
+fn struct_cause(fields: &Fields) -> syn::Result<TokenStream> {
+    Ok(match cause_field(fields)? {
+        Some(member) => quote! {
+            fn cause(&self) -> ::std::option::Option<&dyn ::trycatch::Exception> {
+                ::std::option::Option::Some(&self.#member)
+            }
+        },
+        None => quote!(),
+    })
+}
+
+/// `cause` method matching on the variants that have a field marked with `#[cause]`
+fn enum_cause(data: &DataEnum) -> syn::Result<TokenStream> {
+    let mut arms = vec![];
+    for variant in &data.variants {
+        if let Some(member) = cause_field(&variant.fields)? {
+            let ident = &variant.ident;
+            arms.push(quote! {
+                Self::#ident { #member: cause, .. } => {
+                    ::std::option::Option::Some(cause as &dyn ::trycatch::Exception)
+                }
+            });
+        }
+    }
+    if arms.is_empty() {
+        return Ok(quote!());
+    }
+    Ok(quote! {
+        #[allow(unreachable_patterns)]
+        fn cause(&self) -> ::std::option::Option<&dyn ::trycatch::Exception> {
+            match self {
+                #(#arms)*
+                _ => ::std::option::Option::None,
+            }
+        }
+    })
+}
+
+/// `variant_name` and `kind` methods of enums
+fn enum_variants(data: &DataEnum, name: &str) -> TokenStream {
+    let idents: Vec<_> = data.variants.iter().map(|variant| &variant.ident).collect();
+    let variant_names = idents.iter().map(|ident| ident.to_string());
+    let kinds = idents.iter().map(|ident| format!("{}::{}", name, ident));
+    quote! {
+        fn variant_name(&self) -> ::std::option::Option<&'static str> {
+            ::std::option::Option::Some(match self {
+                #(Self::#idents { .. } => #variant_names,)*
+            })
+        }
+        fn kind(&self) -> &'static str {
+            match self {
+                #(Self::#idents { .. } => #kinds,)*
+            }
+        }
+    }
+}
+
+/// `message` body matching on the variants that have a message template
+fn enum_message(data: &DataEnum) -> syn::Result<TokenStream> {
+    let mut arms = vec![];
+    for variant in &data.variants {
+        let options = Options::parse(&variant.attrs, &["message"])?;
+        if let Some(template) = &options.message {
+            let ident = &variant.ident;
+            let (pattern, format) = message_format(template, &variant.fields)?;
+            arms.push(quote!(Self::#ident #pattern => #format,));
+        }
+    }
+    if arms.is_empty() {
+        return Ok(quote!());
+    }
+    Ok(quote! {
+        match self {
+            #(#arms)*
+            _ => ::std::option::Option::None,
+        }
+    })
+}
+
+/// Pattern binding the fields used by the message template and the expression formatting it.\
+/// `{field}` refers to a named field and `{0}` to a tuple field, format specs are kept as is.
+fn message_format(template: &LitStr, fields: &Fields) -> syn::Result<(TokenStream, TokenStream)> {
+    let value = template.value();
+    let mut format = String::new();
+    let mut used: Vec<(Member, Ident)> = vec![];
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        format.push(c);
+        if c == '}' && chars.peek() == Some(&'}') {
+            format.push(chars.next().unwrap());
+        }
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            format.push(chars.next().unwrap());
+            continue;
+        }
+        let mut argument = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '}' || c == ':' {
+                break;
+            }
+            argument.push(c);
+            chars.next();
+        }
+        let argument = argument.trim();
+        let field = fields
+            .iter()
+            .enumerate()
+            .find(|(index, field)| match &field.ident {
+                Some(ident) => ident == argument,
+                None => index.to_string() == argument,
+            });
+        let (index, field) = match field {
+            Some(field) => field,
+            None if argument.is_empty() => {
+                return Err(Error::new_spanned(
+                    template,
+                    "message placeholders must name a field, like `{field}` or `{0}`",
+                ))
+            }
+            None => {
+                return Err(Error::new_spanned(
+                    template,
+                    format!("no field `{}` to use in the message", argument),
+                ))
+            }
+        };
+        let binding = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("_{}", index),
+        };
+        format.push_str(&binding.to_string());
+        if !used.iter().any(|(_, used)| *used == binding) {
+            used.push((member(index, field), binding));
+        }
+    }
+    let patterns = used.iter().map(|(member, binding)| match member {
+        Member::Named(_) => quote!(#binding),
+        Member::Unnamed(_) => quote!(#member: #binding),
+    });
+    let bindings: Vec<_> = used.iter().map(|(_, binding)| binding).collect();
+    Ok((
+        quote!({ #(#patterns,)* .. }),
+        quote! {
+            ::std::option::Option::Some(::std::format!(#format, #(#bindings = #bindings),*))
+        },
+    ))
+}
+
+/// The field marked with `#[cause]`, at most one per struct or variant
+fn cause_field(fields: &Fields) -> syn::Result<Option<Member>> {
+    let mut cause = None;
+    for (index, field) in fields.iter().enumerate() {
+        for attribute in field.attrs.iter().filter(|a| a.path().is_ident("cause")) {
+            attribute.meta.require_path_only()?;
+            if cause.is_some() {
+                return Err(Error::new_spanned(
+                    attribute,
+                    "only one field can be marked with #[cause]",
+                ));
+            }
+            cause = Some(member(index, field));
+        }
+    }
+    Ok(cause)
+}
+
+fn member(index: usize, field: &Field) -> Member {
+    match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(index.into()),
+    }
+}
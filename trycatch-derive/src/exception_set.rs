@@ -0,0 +1,45 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        Data::Struct(data) => return Err(not_an_enum(data.struct_token.span)),
+        Data::Union(data) => return Err(not_an_enum(data.union_token.span)),
+    };
+    let mut downcasts = vec![];
+    for variant in &data.variants {
+        let exception =
+            match &variant.fields {
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+                _ => return Err(Error::new_spanned(
+                    variant,
+                    "ExceptionSet variants must wrap exactly one exception: `Variant(Exception)`",
+                )),
+            };
+        let ident = &variant.ident;
+        downcasts.push(quote! {
+            let exception = match <::std::boxed::Box<dyn ::trycatch::Exception> as ::trycatch::ExceptionDowncast>::try_downcast::<#exception>(exception) {
+                ::std::result::Result::Ok(exception) => return Self::#ident(exception),
+                ::std::result::Result::Err(exception) => exception,
+            };
+        });
+    }
+
+    let ident = &input.ident;
+    let generics = crate::static_generics(input)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::std::convert::From<::std::boxed::Box<dyn ::trycatch::Exception>> for #ident #ty_generics #where_clause {
+            fn from(exception: ::std::boxed::Box<dyn ::trycatch::Exception>) -> Self {
+                #(#downcasts)*
+                ::trycatch::__private::resume(::trycatch::CatchError::Exception(exception))
+            }
+        }
+    })
+}
+
+fn not_an_enum(span: proc_macro2::Span) -> Error {
+    Error::new(span, "ExceptionSet can only be derived for enums")
+}
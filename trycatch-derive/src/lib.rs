@@ -1,31 +1,56 @@
-use proc_macro::*;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, ItemFn};
 
-#[proc_macro_derive(Exception)]
+mod exception;
+mod exception_set;
+mod throws;
+
+#[proc_macro_derive(Exception, attributes(cause, exception))]
 pub fn derive_exception(item: TokenStream) -> TokenStream {
-    (|| -> Result<TokenStream, Box<dyn std::error::Error>> {
-        let mut item = item.into_iter();
-        // walk the items till we find a struct or an enum ident
-        while !is_struct_or_enum(item.next()) {}
-        let ident = item.next().ok_or("Could not find identifier")?.to_string();
-        let impl_exception = format!(
-            "impl Exception for {0} {{
-                fn name(&self) -> &'static str {{
-                    \"{0}\"
-                }}
-                fn into_any(self: Box<Self>) -> Box<dyn ::std::any::Any> {{
-                    self
-                }}
-             }}",
-            ident
-        );
-        impl_exception.parse().map_err(Into::into)
-    })()
-    .unwrap()
+    let input = parse_macro_input!(item as DeriveInput);
+    exception::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ExceptionSet)]
+pub fn derive_exception_set(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    exception_set::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-fn is_struct_or_enum(next: Option<TokenTree>) -> bool {
-    if next.is_none() {
-        return false;
+/// Declare the exceptions a function can throw, `#[throws(A, B)]`.\
+/// Other exceptions escaping the function are replaced by `trycatch::UndeclaredException`, and
+/// reported on stderr in debug builds, see `trycatch::set_report_undeclared`.\
+/// A companion `<name>_checked` function with the same arguments calls the function and returns
+/// the declared exceptions as errors, `Result<T, A>`, `Result<T, OneOf2<A, B>>` or
+/// `Result<T, OneOf3<A, B, C>>`.\
+/// Associated functions without a `self` receiver end the list with `method`,
+/// `#[throws(A, method)]`, so the companion calls `Self::<name>`. The attribute can't be used in
+/// trait impls, the companion is not a member of the trait.
+#[proc_macro_attribute]
+pub fn throws(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let declared = parse_macro_input!(attr as throws::Declared);
+    let item = parse_macro_input!(item as ItemFn);
+    throws::expand(declared, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generics of the impl, exceptions are `'static` so lifetime parameters are rejected and type
+/// parameters are bound by `'static`
+fn static_generics(input: &DeriveInput) -> syn::Result<syn::Generics> {
+    let mut generics = input.generics.clone();
+    if let Some(lifetime) = generics.lifetimes().next() {
+        return Err(syn::Error::new_spanned(
+            lifetime,
+            "exceptions must be 'static, they can't have lifetime parameters",
+        ));
+    }
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!('static));
     }
-    matches!(next.unwrap(), TokenTree::Ident(ident) if ident.to_string() == "struct" || ident.to_string() == "enum")
+    Ok(generics)
 }
@@ -0,0 +1,177 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::{Error, FnArg, GenericParam, Ident, ItemFn, ReturnType, Signature, Token, Type};
+
+/// Arguments of `#[throws(..)]`, the exception types followed by `method` for associated
+/// functions
+pub struct Declared {
+    types: Vec<Type>,
+    method: bool,
+}
+
+impl syn::parse::Parse for Declared {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut types: Vec<Type> = Punctuated::<Type, Token![,]>::parse_terminated(input)?
+            .into_iter()
+            .collect();
+        let method = matches!(
+            types.last(),
+            Some(Type::Path(ty)) if ty.qself.is_none() && ty.path.is_ident("method")
+        );
+        if method {
+            types.pop();
+        }
+        Ok(Declared { types, method })
+    }
+}
+
+pub fn expand(declared: Declared, item: ItemFn) -> syn::Result<TokenStream> {
+    let Declared { types, method } = declared;
+    let (error, catch) = match types.as_slice() {
+        [a] => (quote!(#a), quote!(::trycatch::catch_only::<#a, _>)),
+        [a, b] => (
+            quote!(::trycatch::OneOf2<#a, #b>),
+            quote!(::trycatch::catch_only2::<#a, #b, _>),
+        ),
+        [a, b, c] => (
+            quote!(::trycatch::OneOf3<#a, #b, #c>),
+            quote!(::trycatch::catch_only3::<#a, #b, #c, _>),
+        ),
+        _ => {
+            return Err(Error::new_spanned(
+                &item.sig,
+                "#[throws(..)] expects between one and three exception types",
+            ))
+        }
+    };
+    if let Some(asyncness) = &item.sig.asyncness {
+        return Err(Error::new_spanned(
+            asyncness,
+            "#[throws(..)] can't be used on async functions",
+        ));
+    }
+    if let Some(constness) = &item.sig.constness {
+        return Err(Error::new_spanned(
+            constness,
+            "#[throws(..)] can't be used on const functions",
+        ));
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+    let ident = &sig.ident;
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    // `impl Trait` can't be written as the return type of a closure, leave it to inference
+    let closure_output = match &sig.output {
+        ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_)) => quote!(),
+        _ => quote!(-> #output),
+    };
+    let body = quote! {
+        ::trycatch::__private::declared(
+            ::core::concat!(::core::module_path!(), "::", ::core::stringify!(#ident)),
+            &[#(::core::any::TypeId::of::<#types>()),*],
+            move || #closure_output #block,
+        )
+    };
+    let names: Vec<_> = types.iter().map(|ty| quote!(#ty).to_string()).collect();
+    let doc = format!("Throws `{}`.", names.join("`, `"));
+
+    let mut checked = sig.clone();
+    // Spanned on the attribute, in a trait impl the error is that the companion isn't a member
+    // of the trait
+    checked.ident = format_ident!("{}_checked", ident, span = Span::call_site());
+    checked.output = syn::parse_quote!(-> ::core::result::Result<#output, #error>);
+    let call = call(&mut checked, ident, method)?;
+    let checked_doc = format!(
+        "Checked version of `{}`, its declared exceptions are returned as errors.",
+        ident
+    );
+
+    let separator = if attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+        quote!(#[doc = ""])
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #separator
+        #[doc = #doc]
+        #vis #sig {
+            #body
+        }
+
+        #[doc = #checked_doc]
+        #vis #checked {
+            #catch(::trycatch::__private::AssertUnwindSafe(move || #call))
+        }
+    })
+}
+
+/// Call of the annotated function from its checked version, the arguments of `checked` are
+/// renamed so patterns like `(a, b): (u8, u8)` can be forwarded.\
+/// Methods and functions declared with `method` are called through `Self::`, anything else is
+/// a free function.
+fn call(checked: &mut Signature, ident: &Ident, method: bool) -> syn::Result<TokenStream> {
+    let mut receiver = false;
+    let mut impl_trait = false;
+    let mut args = Vec::new();
+    for (index, input) in checked.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(_) => {
+                receiver = true;
+                args.push(quote!(self));
+            }
+            FnArg::Typed(arg) => {
+                let name = format_ident!("__arg{}", index);
+                impl_trait |= mentions(&arg.ty.to_token_stream(), "impl");
+                *arg.pat = syn::parse_quote!(#name);
+                args.push(quote!(#name));
+            }
+        }
+    }
+    if !receiver && !method && mentions(&checked.to_token_stream(), "Self") {
+        return Err(Error::new_spanned(
+            checked.fn_token,
+            "associated functions without a `self` receiver are declared with \
+             #[throws(.., method)]",
+        ));
+    }
+    let path = if receiver || method {
+        quote!(Self::#ident)
+    } else {
+        quote!(#ident)
+    };
+    // Generic arguments can't be given explicitly when `impl Trait` arguments are used
+    let params: Vec<_> = checked
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(ty) => Some(ty.ident.clone()),
+            GenericParam::Const(constant) => Some(constant.ident.clone()),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    Ok(if params.is_empty() || impl_trait {
+        quote!(#path(#(#args),*))
+    } else {
+        quote!(#path::<#(#params),*>(#(#args),*))
+    })
+}
+
+fn mentions(tokens: &TokenStream, name: &str) -> bool {
+    tokens.clone().into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions(&group.stream(), name),
+        _ => false,
+    })
+}